@@ -0,0 +1,57 @@
+//! Compares per-sample iteration with block rendering on an 8 channel chip.
+//! Run with "cargo bench", timings depend on the machine so they're only printed.
+
+use soundchip::prelude::*;
+use std::time::{Duration, Instant};
+
+const SAMPLE_RATE: u32 = 44100;
+// About 4 seconds, in whole buffers.
+const FRAMES: usize = 512 * 345;
+// The fastest run is reported, to reduce noise from the rest of the system.
+const RUNS: usize = 10;
+
+fn chip() -> SoundChip {
+    let mut chip = SoundChip::new_msx_scc(SAMPLE_RATE);
+    chip.channel_init_all(true);
+    for (i, channel) in chip.channels.iter_mut().enumerate() {
+        channel.set_note(3 + (i % 3) as i32, Note::C);
+        channel.set_volume(0.5);
+    }
+    chip.channels[0].set_noise(true);
+    chip
+}
+
+fn measure(name: &str, mut render: impl FnMut(&mut SoundChip, usize) -> f32) -> Duration {
+    let mut elapsed = Duration::MAX;
+    let mut checksum = 0.0;
+    for _ in 0..RUNS {
+        let mut chip = chip();
+        let start = Instant::now();
+        checksum = render(&mut chip, FRAMES);
+        elapsed = elapsed.min(start.elapsed());
+    }
+    let per_frame = elapsed.as_nanos() as f64 / FRAMES as f64;
+    println!("{name:>12}: {elapsed:>10.2?}, {per_frame:.1} ns per frame (checksum {checksum})");
+    elapsed
+}
+
+fn main() {
+    let iter = measure("iter", |chip, frames| {
+        chip.iter(frames)
+            .map(|s| s.left as f32 + s.right as f32)
+            .sum()
+    });
+    let block = measure("render_into", |chip, frames| {
+        let mut buffer = [0.0; 1024];
+        let mut sum = 0.0;
+        for _ in 0..frames / 512 {
+            chip.render_into(&mut buffer);
+            sum += buffer.iter().sum::<f32>();
+        }
+        sum
+    });
+    println!(
+        "render_into speed-up: {:.2}x",
+        iter.as_secs_f64() / block.as_secs_f64()
+    );
+}
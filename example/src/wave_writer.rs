@@ -1,15 +1,17 @@
-use hound::{WavSpec, WavWriter};
 use mini_sdl::SdlResult;
-use soundchip::prelude::Sample;
-use std::{env::var_os, fs::File, io::BufWriter, path::PathBuf};
+use soundchip::{
+    prelude::Sample,
+    wav::{WavFormat, WavWriter},
+};
+use std::{env::var_os, path::PathBuf};
 
 pub struct WaveWriter {
-    writer: Option<WavWriter<BufWriter<File>>>,
+    target_file: Option<PathBuf>,
+    writer: WavWriter,
 }
 
 impl WaveWriter {
     pub fn new(sample_rate: u32) -> Self {
-        // Writing in mono for debugging simplicity. Ensure no pan is set in the channel!
         let target_file: Option<PathBuf> = match var_os("CARGO_MANIFEST_DIR") {
             Some(os_var) => {
                 let dir: PathBuf = os_var.into();
@@ -18,36 +20,25 @@ impl WaveWriter {
             None => None,
         };
         println!("Saving wav file to: {:?}", target_file);
-        let wav_spec = WavSpec {
-            channels: 1,
-            sample_rate,
-            bits_per_sample: 16,
-            sample_format: hound::SampleFormat::Int,
-        };
-        let writer = if let Some(target_file) = target_file {
-            WavWriter::create(target_file, wav_spec).ok()
-        } else {
-            None
-        };
-        Self { writer }
+        Self {
+            target_file,
+            writer: WavWriter::new(WavFormat::Int16, sample_rate, 2),
+        }
     }
 
-    pub fn write(&mut self, sample:Sample<i16>) -> SdlResult {
-        if let Some(writer) = &mut self.writer {
-            writer
-                .write_sample(i16::from(sample.left))
-                .map_err(|e| e.to_string())?;
+    pub fn write(&mut self, sample: Sample<i16>) -> SdlResult {
+        if self.target_file.is_some() {
+            self.writer.write(sample);
         }
         Ok(())
     }
 
-    pub fn save_file(self) -> SdlResult{
+    pub fn save_file(self) -> SdlResult {
         // I have this path set to a ram disk on my machine,
         // since I'm saving the wave file for debugging purposes.
-        if let Some(writer) = self.writer {
-            writer.finalize().map_err(|e| e.to_string())?;
+        if let Some(target_file) = self.target_file {
+            std::fs::write(target_file, self.writer.to_bytes()).map_err(|e| e.to_string())?;
         }
         Ok(())
     }
-
 }
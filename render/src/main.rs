@@ -0,0 +1,321 @@
+//! Renders a song or sound description file to a WAV file, without an audio device.
+//!
+//! Supported inputs:
+//! - `.mml`: One MML string per line, each line plays on its own channel. Lines starting
+//!   with `;` are comments.
+//! - `.mid` / `.midi`: Standard MIDI Files, using the default instrument map.
+//! - `.json`: A single instrument note, see [InstrumentFile].
+
+use serde::Deserialize;
+use soundchip::{
+    midi::{MidiFile, MidiPlayer},
+    mml::MmlPlayer,
+    prelude::*,
+    wav::{WavFormat, WavWriter},
+};
+use std::{path::PathBuf, process::ExitCode};
+
+const USAGE: &str = "\
+Usage: soundchip-render <input> [options]
+
+Options:
+  -o, --output <file>     Output WAV file. Default is the input with a .wav extension.
+  -d, --duration <secs>   Renders a fixed duration instead of stopping when playback ends.
+  -t, --tail <secs>       Maximum time to let sounds decay after playback ends. Default is 2.
+  -r, --rate <hz>         Sample rate. Default is 44100.
+  -c, --chip <name>       msx, msx-scc or nes. Default is msx for MML and nes for MIDI.
+                          Ignored by instrument files, which contain their own specs.
+  -f, --format <format>   8, 16, 24 or f32. Default is 16.
+  -h, --help              Shows this message.";
+
+// Safety limit when rendering until playback ends.
+const MAX_DURATION: f64 = 3600.0;
+
+struct Options {
+    input: PathBuf,
+    output: PathBuf,
+    duration: Option<f64>,
+    tail: f64,
+    sample_rate: u32,
+    chip: Option<String>,
+    format: WavFormat,
+}
+
+/// A serialized instrument: chip specs and a sound, played as a single note.
+#[derive(Deserialize)]
+struct InstrumentFile {
+    specs: SpecsChip,
+    sound: Sound,
+    /// MIDI note, C4 is 60.
+    #[serde(default = "default_note")]
+    note: f32,
+    /// Seconds before the note is released.
+    #[serde(default = "default_hold")]
+    hold: f64,
+}
+
+fn default_note() -> f32 {
+    60.0
+}
+
+fn default_hold() -> f64 {
+    0.5
+}
+
+// The different kinds of input, all producing samples from a SoundChip.
+enum Renderer {
+    Mml(Box<MmlPlayer>),
+    Midi(Box<MidiPlayer>),
+    Instrument { chip: Box<SoundChip>, hold: usize },
+}
+
+impl Renderer {
+    fn chip(&self) -> &SoundChip {
+        match self {
+            Renderer::Mml(player) => &player.chip,
+            Renderer::Midi(player) => &player.chip,
+            Renderer::Instrument { chip, .. } => chip,
+        }
+    }
+
+    fn is_playing(&self) -> bool {
+        match self {
+            Renderer::Mml(player) => player.is_playing(),
+            Renderer::Midi(player) => player.is_playing(),
+            Renderer::Instrument { hold, .. } => *hold > 0,
+        }
+    }
+
+    fn process_sample(&mut self) -> Sample<i16> {
+        match self {
+            Renderer::Mml(player) => player.process_sample(),
+            Renderer::Midi(player) => player.process_sample(),
+            Renderer::Instrument { chip, hold } => {
+                if *hold > 0 {
+                    *hold -= 1;
+                    if *hold == 0 {
+                        chip.channels[0].release();
+                    }
+                }
+                chip.process_sample()
+            }
+        }
+    }
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let result = parse_args(&args).and_then(|options| match options {
+        Some(options) => run(&options),
+        None => {
+            println!("{}", USAGE);
+            Ok(())
+        }
+    });
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(message) => {
+            eprintln!("{}", message);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run(options: &Options) -> Result<(), String> {
+    let mut renderer = load(options)?;
+    let mut writer = WavWriter::new(options.format, options.sample_rate, 2);
+    let sample_rate = options.sample_rate as f64;
+
+    if let Some(duration) = options.duration {
+        for _ in 0..(duration * sample_rate) as usize {
+            writer.write(renderer.process_sample());
+        }
+    } else {
+        // Renders until playback ends, then until all channels stop or the tail is over.
+        let max_samples = (MAX_DURATION * sample_rate) as usize;
+        let tail_samples = (options.tail * sample_rate) as usize;
+        let mut tail = 0;
+        while writer.frames() < max_samples {
+            if !renderer.is_playing() {
+                let channels_playing = renderer.chip().channels.iter().any(|c| c.is_playing());
+                if !channels_playing || tail >= tail_samples {
+                    break;
+                }
+                tail += 1;
+            }
+            writer.write(renderer.process_sample());
+        }
+    }
+
+    std::fs::write(&options.output, writer.to_bytes())
+        .map_err(|e| format!("Can't write {}: {}", options.output.display(), e))?;
+    println!(
+        "Rendered {:.2} seconds to {}",
+        writer.frames() as f64 / sample_rate,
+        options.output.display()
+    );
+    Ok(())
+}
+
+fn load(options: &Options) -> Result<Renderer, String> {
+    let extension = options
+        .input
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+    let read_error = |e: std::io::Error| format!("Can't read {}: {}", options.input.display(), e);
+    match extension.as_str() {
+        "mml" => {
+            let source = std::fs::read_to_string(&options.input).map_err(read_error)?;
+            let tracks: Vec<&str> = source
+                .lines()
+                .map(|line| line.trim())
+                .filter(|line| !line.is_empty() && !line.starts_with(';'))
+                .collect();
+            let default_chip = if tracks.len() > 3 { "msx-scc" } else { "msx" };
+            let chip = new_chip(options, default_chip)?;
+            let mut player = MmlPlayer::new(chip);
+            player.play(&tracks).map_err(|e| e.to_string())?;
+            Ok(Renderer::Mml(Box::new(player)))
+        }
+        "mid" | "midi" => {
+            let data = std::fs::read(&options.input).map_err(read_error)?;
+            let file = MidiFile::parse(&data).map_err(|e| e.to_string())?;
+            let chip = new_chip(options, "nes")?;
+            let mut player = MidiPlayer::new(chip, &file);
+            player.play();
+            Ok(Renderer::Midi(Box::new(player)))
+        }
+        "json" => {
+            let source = std::fs::read_to_string(&options.input).map_err(read_error)?;
+            let file: InstrumentFile = serde_json::from_str(&source)
+                .map_err(|e| format!("Invalid instrument file: {}", e))?;
+            let mut chip = SoundChip::new(options.sample_rate);
+            let index = chip.add_channel(file.specs);
+            let channel = &mut chip.channels[index];
+            channel.play_sound(&file.sound, false);
+            channel.set_midi_note(file.note);
+            let hold = ((file.hold * options.sample_rate as f64) as usize).max(1);
+            Ok(Renderer::Instrument {
+                chip: Box::new(chip),
+                hold,
+            })
+        }
+        _ => Err(format!(
+            "Unsupported input file: {}\n\n{}",
+            options.input.display(),
+            USAGE
+        )),
+    }
+}
+
+fn new_chip(options: &Options, default: &str) -> Result<SoundChip, String> {
+    match options.chip.as_deref().unwrap_or(default) {
+        "msx" => Ok(SoundChip::new_msx(options.sample_rate)),
+        "msx-scc" => Ok(SoundChip::new_msx_scc(options.sample_rate)),
+        "nes" => Ok(SoundChip::new_nes(options.sample_rate)),
+        other => Err(format!("Unknown chip: {}", other)),
+    }
+}
+
+// Returns None when the usage was requested.
+fn parse_args(args: &[String]) -> Result<Option<Options>, String> {
+    let mut input = None;
+    let mut output = None;
+    let mut duration = None;
+    let mut tail = 2.0;
+    let mut sample_rate = 44100;
+    let mut chip = None;
+    let mut format = WavFormat::Int16;
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = |name: &str| {
+            args.next()
+                .ok_or_else(|| format!("Missing value for {}", name))
+        };
+        match arg.as_str() {
+            "-h" | "--help" => return Ok(None),
+            "-o" | "--output" => output = Some(PathBuf::from(value(arg)?)),
+            "-d" | "--duration" => duration = Some(parse_number(arg, value(arg)?)?),
+            "-t" | "--tail" => tail = parse_number(arg, value(arg)?)?,
+            "-r" | "--rate" => sample_rate = parse_number(arg, value(arg)?)?,
+            "-c" | "--chip" => chip = Some(value(arg)?.to_ascii_lowercase()),
+            "-f" | "--format" => {
+                format = match value(arg)?.as_str() {
+                    "8" => WavFormat::Int8,
+                    "16" => WavFormat::Int16,
+                    "24" => WavFormat::Int24,
+                    "f32" => WavFormat::Float32,
+                    other => return Err(format!("Unknown format: {}", other)),
+                }
+            }
+            other if other.starts_with('-') => {
+                return Err(format!("Unknown option: {}\n\n{}", other, USAGE))
+            }
+            other => input = Some(PathBuf::from(other)),
+        }
+    }
+
+    let input: PathBuf = input.ok_or_else(|| USAGE.to_string())?;
+    let output = output.unwrap_or_else(|| input.with_extension("wav"));
+    Ok(Some(Options {
+        input,
+        output,
+        duration,
+        tail,
+        sample_rate,
+        chip,
+        format,
+    }))
+}
+
+fn parse_number<T: std::str::FromStr>(name: &str, value: &str) -> Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("Invalid value for {}: {}", name, value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use soundchip::wav::WavFile;
+
+    fn args(list: &[&str]) -> Vec<String> {
+        list.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn help_is_not_an_error() {
+        assert!(parse_args(&args(&["-h"])).unwrap().is_none());
+        assert!(parse_args(&args(&["song.mml", "--help"]))
+            .unwrap()
+            .is_none());
+        assert!(parse_args(&args(&[])).is_err());
+        assert!(parse_args(&args(&["song.mml", "--bogus"])).is_err());
+    }
+
+    #[test]
+    fn renders_mml_to_wav() {
+        let dir = std::env::temp_dir().join(format!("soundchip-render-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let input = dir.join("tiny.mml");
+        std::fs::write(&input, "; Two channels\nt240 l8 cdeg\nt240 l4 o3 c\n").unwrap();
+
+        let input_arg = input.to_str().unwrap();
+        let options = parse_args(&args(&[input_arg, "-r", "22050", "-t", "0.1"]))
+            .unwrap()
+            .unwrap();
+        assert_eq!(options.output, dir.join("tiny.wav"));
+        run(&options).unwrap();
+
+        let data = std::fs::read(&options.output).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+        let wav = WavFile::parse(&data).unwrap();
+        // Four eighth notes at 240 bpm last half a second, plus at most the tail.
+        let seconds = wav.frames() as f64 / 22050.0;
+        assert!((0.5..=0.61).contains(&seconds), "{} seconds", seconds);
+        assert!(wav.mono().iter().any(|value| value.abs() > 0.01));
+    }
+}
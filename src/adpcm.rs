@@ -0,0 +1,155 @@
+//! 4 bit ADPCM decoders for arcade and FM-era sample chips: OKI MSM5205/MSM6295 and the
+//! Yamaha ADPCM-A (YM2610 "Neo Geo" rhythm channels) and ADPCM-B (delta-T) formats.
+//!
+//! Each byte holds two samples, high nibble first. Decoded values can be played with
+//! [Channel::set_pcm()](crate::prelude::Channel::set_pcm) using [to_pcm()], and the chip's
+//! wavetable steps can match the decoder resolution (i.e. 4096 for the 12 bit formats).
+
+use crate::{prelude::PcmSample, Vec};
+
+/// Typical OKI MSM6295 sample rate, with a 1MHz clock and pin 7 high.
+pub const RATE_OKI: f32 = 7575.0;
+
+/// YM2610 ADPCM-A sample rate (8MHz / 432).
+pub const RATE_ADPCM_A: f32 = 18_518.5;
+
+// Shared by OKI and ADPCM-A.
+const STEP_TABLE: [i32; 49] = [
+    16, 17, 19, 21, 23, 25, 28, 31, 34, 37, 41, 45, 50, 55, 60, 66, 73, 80, 88, 97, 107, 118, 130,
+    143, 157, 173, 190, 209, 230, 253, 279, 307, 337, 371, 408, 449, 494, 544, 598, 658, 724, 796,
+    876, 963, 1060, 1166, 1282, 1411, 1552,
+];
+
+const INDEX_ADJUST_OKI: [i32; 8] = [-1, -1, -1, -1, 2, 4, 6, 8];
+const INDEX_ADJUST_ADPCM_A: [i32; 8] = [-1, -1, -1, -1, 2, 5, 7, 9];
+const STEP_SCALE_ADPCM_B: [i32; 8] = [57, 57, 57, 57, 77, 102, 128, 153];
+
+const STEP_MIN_ADPCM_B: i32 = 127;
+const STEP_MAX_ADPCM_B: i32 = 24576;
+
+/// Supported ADPCM variants.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AdpcmFormat {
+    /// OKI MSM5205 / MSM6295, 12 bit output.
+    Oki,
+    /// Yamaha ADPCM-A, 12 bit output with a wrapping accumulator.
+    AdpcmA,
+    /// Yamaha ADPCM-B (delta-T), 16 bit output.
+    AdpcmB,
+}
+
+/// Decoder state, which persists between nibbles.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AdpcmDecoder {
+    format: AdpcmFormat,
+    signal: i32,
+    step: i32,
+}
+
+impl AdpcmDecoder {
+    /// A new decoder with its initial state.
+    pub fn new(format: AdpcmFormat) -> Self {
+        let mut result = Self {
+            format,
+            signal: 0,
+            step: 0,
+        };
+        result.reset();
+        result
+    }
+
+    /// Restores the initial state, like the chips do when a new sample starts.
+    pub fn reset(&mut self) {
+        self.signal = 0;
+        self.step = match self.format {
+            AdpcmFormat::Oki | AdpcmFormat::AdpcmA => 0,
+            AdpcmFormat::AdpcmB => STEP_MIN_ADPCM_B,
+        };
+    }
+
+    /// Decodes a single 4 bit value, returning the new output from -1.0 to 1.0.
+    pub fn decode_nibble(&mut self, nibble: u8) -> f32 {
+        let magnitude = (nibble & 7) as i32;
+        let negative = nibble & 8 != 0;
+        match self.format {
+            AdpcmFormat::Oki | AdpcmFormat::AdpcmA => {
+                let step = STEP_TABLE[self.step as usize];
+                let mut delta = if self.format == AdpcmFormat::Oki {
+                    // Sum of shifted steps, as in the MSM5205 datasheet
+                    let mut delta = step >> 3;
+                    if magnitude & 1 != 0 {
+                        delta += step >> 2;
+                    }
+                    if magnitude & 2 != 0 {
+                        delta += step >> 1;
+                    }
+                    if magnitude & 4 != 0 {
+                        delta += step;
+                    }
+                    delta
+                } else {
+                    ((2 * magnitude + 1) * step) >> 3
+                };
+                if negative {
+                    delta = -delta;
+                }
+                let (signal, adjust) = if self.format == AdpcmFormat::Oki {
+                    let signal = (self.signal + delta).clamp(-2048, 2047);
+                    (signal, INDEX_ADJUST_OKI)
+                } else {
+                    // 12 bit accumulator wraps around, then is sign extended
+                    let signal = ((self.signal + delta) & 0xFFF) as i16;
+                    (((signal << 4) >> 4) as i32, INDEX_ADJUST_ADPCM_A)
+                };
+                self.signal = signal;
+                self.step = (self.step + adjust[magnitude as usize]).clamp(0, 48);
+                self.signal as f32 / 2048.0
+            }
+            AdpcmFormat::AdpcmB => {
+                let mut delta = ((2 * magnitude + 1) * self.step) >> 3;
+                if negative {
+                    delta = -delta;
+                }
+                self.signal = (self.signal + delta).clamp(-32768, 32767);
+                self.step = ((self.step * STEP_SCALE_ADPCM_B[magnitude as usize]) >> 6)
+                    .clamp(STEP_MIN_ADPCM_B, STEP_MAX_ADPCM_B);
+                self.signal as f32 / 32768.0
+            }
+        }
+    }
+}
+
+/// Decodes an entire buffer from the initial state, two samples per byte.
+pub fn decode(format: AdpcmFormat, data: &[u8]) -> Vec<f32> {
+    let mut decoder = AdpcmDecoder::new(format);
+    let mut result = Vec::with_capacity(data.len() * 2);
+    for byte in data {
+        result.push(decoder.decode_nibble(byte >> 4));
+        result.push(decoder.decode_nibble(byte & 15));
+    }
+    result
+}
+
+/// Decodes an entire buffer into a one-shot [PcmSample] playing at "sample_rate".
+pub fn to_pcm(format: AdpcmFormat, data: &[u8], sample_rate: f32) -> PcmSample {
+    PcmSample::new(decode(format, data), sample_rate)
+}
+
+#[test]
+fn adpcm_decoding() {
+    // Smallest positive steps from the initial state.
+    assert_eq!(
+        decode(AdpcmFormat::Oki, &[0x00]),
+        [2.0 / 2048.0, 4.0 / 2048.0]
+    );
+    assert_eq!(decode(AdpcmFormat::AdpcmA, &[0x08]), [2.0 / 2048.0, 0.0]);
+    assert_eq!(decode(AdpcmFormat::AdpcmB, &[0x08]), [15.0 / 32768.0, 0.0]);
+
+    // Large steps grow the step size, OKI clamps while ADPCM-A wraps around.
+    let oki = decode(AdpcmFormat::Oki, &[0x77; 32]);
+    assert_eq!(*oki.last().unwrap(), 2047.0 / 2048.0);
+    let adpcm_a = decode(AdpcmFormat::AdpcmA, &[0x77; 32]);
+    assert!(adpcm_a.iter().any(|value| *value < 0.0));
+    let adpcm_b = decode(AdpcmFormat::AdpcmB, &[0x77; 64]);
+    assert_eq!(*adpcm_b.last().unwrap(), 32767.0 / 32768.0);
+}
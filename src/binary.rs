@@ -0,0 +1,286 @@
+//! Compact, versioned binary encoding for [Sound](crate::prelude::Sound), [SpecsChip](crate::prelude::SpecsChip) and
+//! [Envelope](crate::prelude::Envelope), meant for embedded targets where data lives in flash memory.
+//!
+//! Every top level item starts with a 4 byte header: the "SC" magic bytes, the format version
+//! and the kind of item. Lengths and knot times use unsigned LEB128 varints. Knot times are
+//! quantized to milliseconds, and knot values keep the resolution of their type ([Normal] and
+//! [NormalSigned] use 16 bits, f32 values use 8.8 fixed point).
+//!
+//! [EnvelopeView] and [SoundView] validate the data once, then borrow it: knots are decoded on
+//! the fly while iterating, without allocating. Use their "to_*" methods to obtain the regular,
+//! heap allocated types.
+
+mod envelope;
+pub use envelope::*;
+
+mod sound;
+pub use sound::*;
+
+mod specs;
+pub use specs::*;
+
+use crate::{
+    prelude::{ChipError, KnotValue, Normal, NormalSigned},
+    Vec,
+};
+
+/// Current format version. Data with a different version is rejected with
+/// [ChipError::UnsupportedVersion].
+pub const BINARY_VERSION: u8 = 4;
+
+const MAGIC: &[u8; 2] = b"SC";
+
+/// The kind of item in a binary header.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BinaryKind {
+    Envelope = 1,
+    Sound = 2,
+    Specs = 3,
+}
+
+/// Knot values that can be stored in the binary format.
+pub trait BinaryValue: KnotValue {
+    /// Encoded size in bytes.
+    const SIZE: usize;
+    fn write(self, writer: &mut Writer);
+    fn read(reader: &mut Reader) -> Result<Self, ChipError>;
+}
+
+impl BinaryValue for Normal {
+    const SIZE: usize = 2;
+    fn write(self, writer: &mut Writer) {
+        writer.u16(self.raw());
+    }
+    fn read(reader: &mut Reader) -> Result<Self, ChipError> {
+        Ok(Normal::from_raw(reader.u16()?))
+    }
+}
+
+impl BinaryValue for NormalSigned {
+    const SIZE: usize = 2;
+    fn write(self, writer: &mut Writer) {
+        writer.u16(self.raw() as u16);
+    }
+    fn read(reader: &mut Reader) -> Result<Self, ChipError> {
+        Ok(NormalSigned::from_raw(reader.u16()? as i16))
+    }
+}
+
+/// Stored as 8.8 fixed point, from -128.0 to 127.996.
+impl BinaryValue for f32 {
+    const SIZE: usize = 2;
+    fn write(self, writer: &mut Writer) {
+        let fixed = libm::roundf(self * 256.0).clamp(i16::MIN as f32, i16::MAX as f32);
+        writer.u16(fixed as i16 as u16);
+    }
+    fn read(reader: &mut Reader) -> Result<Self, ChipError> {
+        Ok(reader.u16()? as i16 as f32 / 256.0)
+    }
+}
+
+/// Appends binary values to a byte buffer.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Writer {
+    bytes: Vec<u8>,
+}
+
+impl Writer {
+    /// A writer starting with the header for an item kind.
+    pub fn new(kind: BinaryKind) -> Self {
+        let mut result = Self::default();
+        result.bytes.extend_from_slice(MAGIC);
+        result.u8(BINARY_VERSION);
+        result.u8(kind as u8);
+        result
+    }
+
+    /// The encoded bytes.
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.bytes
+    }
+
+    pub fn u8(&mut self, value: u8) {
+        self.bytes.push(value);
+    }
+
+    pub fn bool(&mut self, value: bool) {
+        self.u8(value as u8);
+    }
+
+    pub fn u16(&mut self, value: u16) {
+        self.bytes.extend_from_slice(&value.to_le_bytes());
+    }
+
+    pub fn f32(&mut self, value: f32) {
+        self.bytes.extend_from_slice(&value.to_le_bytes());
+    }
+
+    pub fn varint(&mut self, mut value: u32) {
+        loop {
+            let byte = (value & 0x7F) as u8;
+            value >>= 7;
+            if value == 0 {
+                self.u8(byte);
+                return;
+            }
+            self.u8(byte | 0x80);
+        }
+    }
+
+    /// Optional u16, stored as a varint where zero means None.
+    pub fn option_u16(&mut self, value: Option<u16>) {
+        self.varint(value.map_or(0, |value| value as u32 + 1));
+    }
+
+    /// Optional f32, stored as a presence byte followed by the value.
+    pub fn option_f32(&mut self, value: Option<f32>) {
+        self.bool(value.is_some());
+        if let Some(value) = value {
+            self.f32(value);
+        }
+    }
+}
+
+/// Reads binary values from a byte slice. Any read past the end fails with
+/// [ChipError::InvalidBinary].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Reader<'a> {
+    data: &'a [u8],
+    head: usize,
+}
+
+impl<'a> Reader<'a> {
+    /// A reader starting at the beginning of the data, without a header.
+    pub fn new(data: &'a [u8]) -> Self {
+        Self { data, head: 0 }
+    }
+
+    /// A reader positioned after a valid header for an item kind.
+    pub fn with_header(data: &'a [u8], kind: BinaryKind) -> Result<Self, ChipError> {
+        let mut result = Self::new(data);
+        if result.bytes(2)? != MAGIC {
+            return Err(ChipError::InvalidBinary);
+        }
+        let version = result.u8()?;
+        if version != BINARY_VERSION {
+            return Err(ChipError::UnsupportedVersion { version });
+        }
+        if result.u8()? != kind as u8 {
+            return Err(ChipError::InvalidBinary);
+        }
+        Ok(result)
+    }
+
+    /// Current position in bytes.
+    pub fn head(&self) -> usize {
+        self.head
+    }
+
+    pub fn bytes(&mut self, len: usize) -> Result<&'a [u8], ChipError> {
+        let end = self.head.checked_add(len).ok_or(ChipError::InvalidBinary)?;
+        let result = self.data.get(self.head..end).ok_or(ChipError::InvalidBinary)?;
+        self.head = end;
+        Ok(result)
+    }
+
+    pub fn u8(&mut self) -> Result<u8, ChipError> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    pub fn bool(&mut self) -> Result<bool, ChipError> {
+        match self.u8()? {
+            0 => Ok(false),
+            1 => Ok(true),
+            _ => Err(ChipError::InvalidBinary),
+        }
+    }
+
+    pub fn u16(&mut self) -> Result<u16, ChipError> {
+        let bytes = self.bytes(2)?;
+        Ok(u16::from_le_bytes([bytes[0], bytes[1]]))
+    }
+
+    pub fn f32(&mut self) -> Result<f32, ChipError> {
+        let bytes = self.bytes(4)?;
+        Ok(f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    /// Unsigned LEB128, up to 5 bytes.
+    pub fn varint(&mut self) -> Result<u32, ChipError> {
+        let mut result: u32 = 0;
+        for i in 0..5 {
+            let byte = self.u8()?;
+            result |= ((byte & 0x7F) as u32) << (i * 7);
+            if byte & 0x80 == 0 {
+                return Ok(result);
+            }
+        }
+        Err(ChipError::InvalidBinary)
+    }
+
+    pub fn option_u16(&mut self) -> Result<Option<u16>, ChipError> {
+        match self.varint()? {
+            0 => Ok(None),
+            value => u16::try_from(value - 1)
+                .map(Some)
+                .map_err(|_| ChipError::InvalidBinary),
+        }
+    }
+
+    pub fn option_f32(&mut self) -> Result<Option<f32>, ChipError> {
+        if self.bool()? {
+            Ok(Some(self.f32()?))
+        } else {
+            Ok(None)
+        }
+    }
+}
+
+#[test]
+fn binary_round_trip() {
+    use crate::{prelude::*, presets::*};
+    let sound = Sound {
+        waveform: Some(Envelope::from(KNOTS_WAVE_SQUARE)),
+        pitch_env: Some(Envelope::from(KNOTS_PITCH_DOWN).set_loop(LoopKind::Repeat)),
+        volume_env: Some(Envelope::from(KNOTS_VOL_DOWN).set_loop(LoopKind::Echo {
+            loop_in: 1,
+            loop_out: 1,
+            decay: 0.5.into(),
+        })),
+        tremolo: Some(TREMOLO_SUBTLE),
+        ..Default::default()
+    };
+    let bytes = encode_sound(&sound);
+    let view = SoundView::from_bytes(&bytes).unwrap();
+    assert_eq!(view.to_sound(), sound);
+    let square: Vec<Knot<NormalSigned>> = view.waveform.unwrap().knots().collect();
+    assert_eq!(square.len(), KNOTS_WAVE_SQUARE.len());
+
+    for specs in [SPEC_CHIP_PSG_NOISE, SPEC_CHIP_SCC, SPEC_CHIP_NES_NOISE] {
+        let bytes = encode_specs(&specs);
+        assert_eq!(decode_specs(&bytes).unwrap(), specs);
+    }
+
+    // Malformed and mismatched data
+    assert_eq!(
+        SoundView::from_bytes(&encode_specs(&SPEC_CHIP_SCC)).err(),
+        Some(ChipError::InvalidBinary)
+    );
+    assert_eq!(
+        SoundView::from_bytes(&encode_sound(&sound)[..20]).err(),
+        Some(ChipError::InvalidBinary)
+    );
+    let empty = Sound {
+        volume_env: Some(Envelope::from([].as_slice() as &[Knot<Normal>])),
+        ..Default::default()
+    };
+    assert_eq!(decode_sound(&encode_sound(&empty)).err(), Some(ChipError::InvalidBinary));
+    let mut future = encode_sound(&sound);
+    future[2] = BINARY_VERSION + 1;
+    assert_eq!(
+        SoundView::from_bytes(&future).err(),
+        Some(ChipError::UnsupportedVersion {
+            version: BINARY_VERSION + 1
+        })
+    );
+}
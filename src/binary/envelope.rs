@@ -0,0 +1,159 @@
+use super::{BinaryKind, BinaryValue, Reader, Writer};
+use crate::{
+    prelude::{ChipError, Envelope, Interpolation, Knot, LoopKind, Normal},
+    Vec,
+};
+use core::marker::PhantomData;
+
+/// Encodes an envelope as a standalone item, with a header.
+pub fn encode_envelope<T: BinaryValue>(envelope: &Envelope<T>) -> Vec<u8> {
+    let mut writer = Writer::new(BinaryKind::Envelope);
+    write_envelope(&mut writer, envelope);
+    writer.into_bytes()
+}
+
+/// Appends an envelope without a header, for use inside other items.
+pub fn write_envelope<T: BinaryValue>(writer: &mut Writer, envelope: &Envelope<T>) {
+    write_knots(writer, &envelope.knots);
+    match envelope.loop_kind {
+        LoopKind::None => writer.u8(0),
+        LoopKind::Repeat => writer.u8(1),
+        LoopKind::LoopPoints { loop_in, loop_out } => {
+            writer.u8(2);
+            writer.u8(loop_in);
+            writer.u8(loop_out);
+        }
+        LoopKind::Echo {
+            loop_in,
+            loop_out,
+            decay,
+        } => {
+            writer.u8(3);
+            writer.u8(loop_in);
+            writer.u8(loop_out);
+            decay.write(writer);
+        }
+    }
+}
+
+/// Appends a knot count followed by the knots. The interpolation is stored in the lowest
+/// bit of the time.
+pub fn write_knots<T: BinaryValue>(writer: &mut Writer, knots: &[Knot<T>]) {
+    writer.varint(knots.len() as u32);
+    for knot in knots {
+        let millis = libm::roundf(knot.time.max(0.0) * 1000.0) as u32;
+        let step = (knot.interpolation == Interpolation::Step) as u32;
+        writer.varint((millis << 1) | step);
+        knot.value.write(writer);
+    }
+}
+
+/// A validated, borrowed list of encoded knots.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct KnotsView<'a, T: BinaryValue> {
+    data: &'a [u8],
+    len: usize,
+    value: PhantomData<T>,
+}
+
+impl<'a, T: BinaryValue> KnotsView<'a, T> {
+    /// Reads a knot count and validates the knots that follow, advancing the reader.
+    pub fn read(reader: &mut Reader<'a>) -> Result<Self, ChipError> {
+        let len = reader.varint()? as usize;
+        let start = reader.head();
+        let mut scan = reader.clone();
+        for _ in 0..len {
+            scan.varint()?;
+            scan.bytes(T::SIZE)?;
+        }
+        let data = reader.bytes(scan.head() - start)?;
+        Ok(Self {
+            data,
+            len,
+            value: PhantomData,
+        })
+    }
+
+    /// Number of knots.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Decodes the knots one by one.
+    pub fn iter(&self) -> impl Iterator<Item = Knot<T>> + 'a {
+        let mut reader = Reader::new(self.data);
+        (0..self.len).map(move |_| {
+            // Already validated
+            let time = reader.varint().unwrap_or_default();
+            let value = T::read(&mut reader).unwrap_or_default();
+            Knot {
+                time: (time >> 1) as f32 / 1000.0,
+                value,
+                interpolation: if time & 1 == 1 {
+                    Interpolation::Step
+                } else {
+                    Interpolation::Linear
+                },
+            }
+        })
+    }
+
+    /// Decodes all knots into a Vec.
+    pub fn to_vec(&self) -> Vec<Knot<T>> {
+        self.iter().collect()
+    }
+}
+
+/// A validated, borrowed envelope. Knots are decoded while iterating.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EnvelopeView<'a, T: BinaryValue> {
+    pub knots: KnotsView<'a, T>,
+    pub loop_kind: LoopKind,
+}
+
+impl<'a, T: BinaryValue> EnvelopeView<'a, T> {
+    /// Validates a standalone envelope, starting with its header.
+    pub fn from_bytes(data: &'a [u8]) -> Result<Self, ChipError> {
+        let mut reader = Reader::with_header(data, BinaryKind::Envelope)?;
+        Self::read(&mut reader)
+    }
+
+    /// Reads an envelope without a header, advancing the reader. Envelopes without knots
+    /// are rejected.
+    pub fn read(reader: &mut Reader<'a>) -> Result<Self, ChipError> {
+        let knots = KnotsView::read(reader)?;
+        // Envelopes can't be sampled without knots
+        if knots.is_empty() {
+            return Err(ChipError::InvalidBinary);
+        }
+        let loop_kind = match reader.u8()? {
+            0 => LoopKind::None,
+            1 => LoopKind::Repeat,
+            2 => LoopKind::LoopPoints {
+                loop_in: reader.u8()?,
+                loop_out: reader.u8()?,
+            },
+            3 => LoopKind::Echo {
+                loop_in: reader.u8()?,
+                loop_out: reader.u8()?,
+                decay: Normal::read(reader)?,
+            },
+            _ => return Err(ChipError::InvalidBinary),
+        };
+        Ok(Self { knots, loop_kind })
+    }
+
+    /// Decodes the knots one by one.
+    pub fn knots(&self) -> impl Iterator<Item = Knot<T>> + 'a {
+        self.knots.iter()
+    }
+
+    /// Decodes into a regular envelope.
+    pub fn to_envelope(&self) -> Envelope<T> {
+        Envelope::<T>::from(self.knots.to_vec().as_slice()).set_loop(self.loop_kind)
+    }
+}
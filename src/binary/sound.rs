@@ -0,0 +1,148 @@
+use super::{BinaryKind, EnvelopeView, Reader, Writer};
+use crate::{
+    binary::write_envelope,
+    prelude::{ChipError, Normal, NormalSigned, Sound, Tremolo, Vibratto},
+    Vec,
+};
+
+const FLAG_NOISE_ENV: u8 = 1;
+const FLAG_WAVEFORM: u8 = 1 << 1;
+const FLAG_TREMOLO: u8 = 1 << 2;
+const FLAG_VIBRATTO: u8 = 1 << 3;
+const FLAG_VOLUME_ENV: u8 = 1 << 4;
+const FLAG_PITCH_ENV: u8 = 1 << 5;
+
+/// Encodes a sound as a standalone item, with a header.
+pub fn encode_sound(sound: &Sound) -> Vec<u8> {
+    let mut writer = Writer::new(BinaryKind::Sound);
+    writer.f32(sound.volume);
+    writer.f32(sound.pitch);
+    let flags = [
+        (sound.noise_env.is_some(), FLAG_NOISE_ENV),
+        (sound.waveform.is_some(), FLAG_WAVEFORM),
+        (sound.tremolo.is_some(), FLAG_TREMOLO),
+        (sound.vibratto.is_some(), FLAG_VIBRATTO),
+        (sound.volume_env.is_some(), FLAG_VOLUME_ENV),
+        (sound.pitch_env.is_some(), FLAG_PITCH_ENV),
+    ]
+    .iter()
+    .filter(|(present, _)| *present)
+    .fold(0, |flags, (_, flag)| flags | flag);
+    writer.u8(flags);
+
+    if let Some(env) = &sound.noise_env {
+        write_envelope(&mut writer, env);
+    }
+    if let Some(env) = &sound.waveform {
+        write_envelope(&mut writer, env);
+    }
+    if let Some(tremolo) = &sound.tremolo {
+        writer.option_u16(tremolo.steps);
+        writer.f32(tremolo.amplitude);
+        writer.f32(tremolo.frequency);
+    }
+    if let Some(vibratto) = &sound.vibratto {
+        writer.option_u16(vibratto.steps);
+        writer.f32(vibratto.amplitude);
+        writer.f32(vibratto.frequency);
+    }
+    if let Some(env) = &sound.volume_env {
+        write_envelope(&mut writer, env);
+    }
+    if let Some(env) = &sound.pitch_env {
+        write_envelope(&mut writer, env);
+    }
+    writer.into_bytes()
+}
+
+/// Decodes a sound. Same as [SoundView::from_bytes()] followed by [SoundView::to_sound()].
+pub fn decode_sound(data: &[u8]) -> Result<Sound, ChipError> {
+    Ok(SoundView::from_bytes(data)?.to_sound())
+}
+
+/// A validated sound that borrows its envelopes from the encoded data.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SoundView<'a> {
+    pub volume: f32,
+    pub pitch: f32,
+    pub noise_env: Option<EnvelopeView<'a, Normal>>,
+    pub waveform: Option<EnvelopeView<'a, NormalSigned>>,
+    pub tremolo: Option<Tremolo>,
+    pub vibratto: Option<Vibratto>,
+    pub volume_env: Option<EnvelopeView<'a, Normal>>,
+    pub pitch_env: Option<EnvelopeView<'a, f32>>,
+}
+
+impl<'a> SoundView<'a> {
+    /// Validates a sound, starting with its header.
+    pub fn from_bytes(data: &'a [u8]) -> Result<Self, ChipError> {
+        let mut reader = Reader::with_header(data, BinaryKind::Sound)?;
+        let volume = reader.f32()?;
+        let pitch = reader.f32()?;
+        let flags = reader.u8()?;
+        let has = |flag: u8| flags & flag != 0;
+
+        let noise_env = if has(FLAG_NOISE_ENV) {
+            Some(EnvelopeView::read(&mut reader)?)
+        } else {
+            None
+        };
+        let waveform = if has(FLAG_WAVEFORM) {
+            Some(EnvelopeView::read(&mut reader)?)
+        } else {
+            None
+        };
+        let tremolo = if has(FLAG_TREMOLO) {
+            Some(Tremolo {
+                steps: reader.option_u16()?,
+                amplitude: reader.f32()?,
+                frequency: reader.f32()?,
+            })
+        } else {
+            None
+        };
+        let vibratto = if has(FLAG_VIBRATTO) {
+            Some(Vibratto {
+                steps: reader.option_u16()?,
+                amplitude: reader.f32()?,
+                frequency: reader.f32()?,
+            })
+        } else {
+            None
+        };
+        let volume_env = if has(FLAG_VOLUME_ENV) {
+            Some(EnvelopeView::read(&mut reader)?)
+        } else {
+            None
+        };
+        let pitch_env = if has(FLAG_PITCH_ENV) {
+            Some(EnvelopeView::read(&mut reader)?)
+        } else {
+            None
+        };
+        Ok(Self {
+            volume,
+            pitch,
+            noise_env,
+            waveform,
+            tremolo,
+            vibratto,
+            volume_env,
+            pitch_env,
+        })
+    }
+
+    /// Decodes into a regular sound.
+    pub fn to_sound(&self) -> Sound {
+        Sound {
+            volume: self.volume,
+            pitch: self.pitch,
+            noise_env: self.noise_env.map(|env| env.to_envelope()),
+            waveform: self.waveform.map(|env| env.to_envelope()),
+            tremolo: self.tremolo,
+            vibratto: self.vibratto,
+            volume_env: self.volume_env.map(|env| env.to_envelope()),
+            pitch_env: self.pitch_env.map(|env| env.to_envelope()),
+        }
+    }
+}
@@ -0,0 +1,271 @@
+use super::{BinaryKind, KnotsView, Reader, Writer};
+use crate::{binary::write_knots, prelude::*, Vec};
+use alloc::borrow::Cow;
+
+/// Encodes chip specs as a standalone item, with a header.
+pub fn encode_specs(specs: &SpecsChip) -> Vec<u8> {
+    let mut writer = Writer::new(BinaryKind::Specs);
+    writer.option_f32(specs.envelope_rate);
+
+    let wavetable = &specs.wavetable;
+    writer.bool(wavetable.default_waveform.is_some());
+    if let Some(knots) = &wavetable.default_waveform {
+        write_knots(&mut writer, knots);
+    }
+    writer.varint(wavetable.sample_count as u32);
+    writer.bool(wavetable.use_loop);
+    writer.option_u16(wavetable.steps);
+    writer.u8(match wavetable.anti_aliasing {
+        AntiAliasing::None => 0,
+        AntiAliasing::PolyBlep => 1,
+    });
+
+    writer.option_u16(specs.pan.steps);
+    write_pitch(&mut writer, &specs.pitch);
+
+    let volume = &specs.volume;
+    writer.option_u16(volume.steps);
+    writer.f32(volume.attenuation);
+    writer.f32(volume.exponent);
+    writer.f32(volume.gain);
+    writer.bool(volume.clip_negative_values);
+    writer.bool(volume.hardware_envelope.is_some());
+    if let Some(hardware) = &volume.hardware_envelope {
+        writer.u8(hardware.steps);
+        write_divider(&mut writer, &hardware.divider);
+    }
+    writer.bool(volume.table.is_some());
+    if let Some(table) = &volume.table {
+        write_levels(&mut writer, table);
+    }
+    writer.bool(volume.mix_table.is_some());
+    if let Some(mix) = &volume.mix_table {
+        write_levels(&mut writer, &mix.levels);
+        writer.f32(mix.weight);
+    }
+
+    match &specs.noise {
+        SpecsNoise::None => writer.u8(0),
+        SpecsNoise::Melodic {
+            lfsr_length,
+            volume_steps,
+            pitch,
+        } => {
+            writer.u8(1);
+            writer.varint(*lfsr_length as u32);
+            writer.u8(*volume_steps);
+            write_pitch(&mut writer, pitch);
+        }
+        SpecsNoise::Random {
+            volume_steps,
+            pitch,
+        } => {
+            writer.u8(2);
+            writer.u8(*volume_steps);
+            write_pitch(&mut writer, pitch);
+        }
+        SpecsNoise::WaveTable { mix } => {
+            writer.u8(3);
+            writer.f32(*mix);
+        }
+    }
+    writer.u8(match specs.noise_mix {
+        NoiseMix::Overwrite => 0,
+        NoiseMix::And => 1,
+        NoiseMix::Add => 2,
+        NoiseMix::Crossfade => 3,
+        NoiseMix::Ring => 4,
+    });
+
+    writer.varint(specs.output.filters.len() as u32);
+    for filter in specs.output.filters.iter() {
+        writer.u8(match filter.kind {
+            FilterKind::HighPass => 0,
+            FilterKind::LowPass => 1,
+        });
+        writer.f32(filter.cutoff);
+        writer.u8(match filter.poles {
+            FilterPoles::One => 1,
+            FilterPoles::Two => 2,
+        });
+    }
+    writer.into_bytes()
+}
+
+/// Decodes chip specs. The default waveform, if any, is decoded into owned knots.
+pub fn decode_specs(data: &[u8]) -> Result<SpecsChip, ChipError> {
+    let mut reader = Reader::with_header(data, BinaryKind::Specs)?;
+    let envelope_rate = reader.option_f32()?;
+
+    let default_waveform = if reader.bool()? {
+        let knots: KnotsView<NormalSigned> = KnotsView::read(&mut reader)?;
+        // The wavetable can't be generated without knots
+        if knots.is_empty() {
+            return Err(ChipError::InvalidBinary);
+        }
+        Some(Cow::Owned(knots.to_vec()))
+    } else {
+        None
+    };
+    let wavetable = SpecsWavetable {
+        default_waveform,
+        sample_count: reader.varint()? as usize,
+        use_loop: reader.bool()?,
+        steps: reader.option_u16()?,
+        anti_aliasing: match reader.u8()? {
+            0 => AntiAliasing::None,
+            1 => AntiAliasing::PolyBlep,
+            _ => return Err(ChipError::InvalidBinary),
+        },
+    };
+
+    let pan = SpecsPan {
+        steps: reader.option_u16()?,
+    };
+    let pitch = read_pitch(&mut reader)?;
+
+    let volume = SpecsVolume {
+        steps: reader.option_u16()?,
+        attenuation: reader.f32()?,
+        exponent: reader.f32()?,
+        gain: reader.f32()?,
+        clip_negative_values: reader.bool()?,
+        hardware_envelope: if reader.bool()? {
+            Some(SpecsHardwareEnvelope {
+                steps: reader.u8()?,
+                divider: read_divider(&mut reader)?,
+            })
+        } else {
+            None
+        },
+        table: if reader.bool()? {
+            Some(Cow::Owned(read_levels(&mut reader)?))
+        } else {
+            None
+        },
+        mix_table: if reader.bool()? {
+            Some(SpecsMixTable {
+                levels: Cow::Owned(read_levels(&mut reader)?),
+                weight: reader.f32()?,
+            })
+        } else {
+            None
+        },
+    };
+
+    let noise = match reader.u8()? {
+        0 => SpecsNoise::None,
+        1 => SpecsNoise::Melodic {
+            lfsr_length: u16::try_from(reader.varint()?).map_err(|_| ChipError::InvalidBinary)?,
+            volume_steps: reader.u8()?,
+            pitch: read_pitch(&mut reader)?,
+        },
+        2 => SpecsNoise::Random {
+            volume_steps: reader.u8()?,
+            pitch: read_pitch(&mut reader)?,
+        },
+        3 => SpecsNoise::WaveTable { mix: reader.f32()? },
+        _ => return Err(ChipError::InvalidBinary),
+    };
+    let noise_mix = match reader.u8()? {
+        0 => NoiseMix::Overwrite,
+        1 => NoiseMix::And,
+        2 => NoiseMix::Add,
+        3 => NoiseMix::Crossfade,
+        4 => NoiseMix::Ring,
+        _ => return Err(ChipError::InvalidBinary),
+    };
+
+    let filter_count = reader.varint()?;
+    let mut filters = Vec::new();
+    for _ in 0..filter_count {
+        let kind = match reader.u8()? {
+            0 => FilterKind::HighPass,
+            1 => FilterKind::LowPass,
+            _ => return Err(ChipError::InvalidBinary),
+        };
+        let cutoff = reader.f32()?;
+        let poles = match reader.u8()? {
+            1 => FilterPoles::One,
+            2 => FilterPoles::Two,
+            _ => return Err(ChipError::InvalidBinary),
+        };
+        filters.push(SpecsFilter {
+            kind,
+            cutoff,
+            poles,
+        });
+    }
+    let output = SpecsOutput {
+        filters: Cow::Owned(filters),
+    };
+
+    Ok(SpecsChip {
+        envelope_rate,
+        wavetable,
+        pan,
+        pitch,
+        volume,
+        noise,
+        noise_mix,
+        output,
+    })
+}
+
+fn write_pitch(writer: &mut Writer, pitch: &SpecsPitch) {
+    writer.f32(pitch.multiplier);
+    writer.bool(pitch.range.is_some());
+    if let Some(range) = &pitch.range {
+        writer.f32(*range.start());
+        writer.f32(*range.end());
+    }
+    writer.option_u16(pitch.steps);
+    write_divider(writer, &pitch.divider);
+}
+
+fn read_pitch(reader: &mut Reader) -> Result<SpecsPitch, ChipError> {
+    Ok(SpecsPitch {
+        multiplier: reader.f32()?,
+        range: if reader.bool()? {
+            Some(reader.f32()?..=reader.f32()?)
+        } else {
+            None
+        },
+        steps: reader.option_u16()?,
+        divider: read_divider(reader)?,
+    })
+}
+
+fn write_divider(writer: &mut Writer, divider: &Option<SpecsDivider>) {
+    writer.bool(divider.is_some());
+    if let Some(divider) = divider {
+        writer.f32(divider.clock);
+        writer.f32(divider.divisor);
+        writer.u8(divider.bits);
+        writer.bool(divider.plus_one);
+    }
+}
+
+fn read_divider(reader: &mut Reader) -> Result<Option<SpecsDivider>, ChipError> {
+    if !reader.bool()? {
+        return Ok(None);
+    }
+    Ok(Some(SpecsDivider {
+        clock: reader.f32()?,
+        divisor: reader.f32()?,
+        bits: reader.u8()?,
+        plus_one: reader.bool()?,
+    }))
+}
+
+fn write_levels(writer: &mut Writer, levels: &[f32]) {
+    writer.varint(levels.len() as u32);
+    for level in levels {
+        writer.f32(*level);
+    }
+}
+
+fn read_levels(reader: &mut Reader) -> Result<Vec<f32>, ChipError> {
+    let len = reader.varint()?;
+    (0..len).map(|_| reader.f32()).collect()
+}
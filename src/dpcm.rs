@@ -0,0 +1,227 @@
+//! NES DPCM (delta modulation) codec, as used by the APU's DMC channel.
+//!
+//! Each bit of a DPCM stream moves a 7 bit output level up (1) or down (0) by two steps,
+//! which limits how fast the wave can change and gives the DMC its characteristic "slewed"
+//! sound. Streams play at one of 16 fixed rates, selected by a 4 bit register.
+//! Use [Channel::set_dpcm()](crate::prelude::Channel::set_dpcm) to play them.
+
+use crate::{presets::CLOCK_NES, Vec};
+
+/// DMC rate periods in CPU cycles (NTSC), indexed by the 4 bit rate register.
+pub const DMC_PERIODS_NTSC: [u16; 16] = [
+    428, 380, 340, 320, 286, 254, 226, 214, 190, 160, 142, 128, 106, 84, 72, 54,
+];
+
+/// The output level after a power on or reset.
+pub const DMC_DEFAULT_LEVEL: u8 = 64;
+
+/// Playback frequency in Hertz of a DMC rate index (0 to 15).
+pub fn rate_frequency(rate: u8) -> f32 {
+    CLOCK_NES / DMC_PERIODS_NTSC[(rate & 15) as usize] as f32
+}
+
+/// A 1 bit delta stream, with the settings the DMC needs to play it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DpcmSample {
+    /// Delta bits, least significant bit first.
+    pub data: Vec<u8>,
+    /// Rate index, from 0 (slowest) to 15 (fastest).
+    pub rate: u8,
+    /// If true, the stream restarts when it ends. The output level is not reset.
+    pub looping: bool,
+    /// The 7 bit output level when the stream starts, like writing to the DMC's
+    /// direct load register.
+    pub initial_level: u8,
+}
+
+impl Default for DpcmSample {
+    fn default() -> Self {
+        Self {
+            data: Vec::new(),
+            rate: 15,
+            looping: false,
+            initial_level: DMC_DEFAULT_LEVEL,
+        }
+    }
+}
+
+impl DpcmSample {
+    /// Encodes PCM values (-1.0 to 1.0) recorded at "sample_rate" into a delta stream playing at
+    /// the DMC "rate" index. Every bit moves the level towards the source wave, and the data
+    /// is padded to a valid DMC length (16 * n + 1 bytes) with a pattern that holds the level.
+    pub fn encode(pcm: &[f32], sample_rate: f32, rate: u8) -> Self {
+        let rate = rate & 15;
+        let step = sample_rate / rate_frequency(rate);
+        let bit_count = if pcm.is_empty() {
+            0
+        } else {
+            (pcm.len() as f32 / step) as usize
+        };
+        let initial_level = pcm
+            .first()
+            .map(|value| to_level(*value))
+            .unwrap_or(DMC_DEFAULT_LEVEL);
+
+        let mut data = Vec::with_capacity((bit_count / 8) + 17);
+        let mut level = initial_level;
+        let mut byte = 0;
+        for bit in 0..bit_count {
+            // Linear interpolation of the source
+            let position = bit as f32 * step;
+            let index = position as usize;
+            let a = pcm[index];
+            let b = pcm.get(index + 1).copied().unwrap_or(a);
+            let target = to_level(a + ((b - a) * (position - index as f32)));
+
+            let up = target > level;
+            level = step_level(level, up);
+            if up {
+                byte |= 1 << (bit % 8);
+            }
+            if bit % 8 == 7 {
+                data.push(byte);
+                byte = 0;
+            }
+        }
+        if bit_count % 8 != 0 {
+            // Completes the last byte alternating up and down
+            for bit in (bit_count % 8)..8 {
+                if bit % 2 == 0 {
+                    byte |= 1 << bit;
+                }
+            }
+            data.push(byte);
+        }
+        while data.len() % 16 != 1 {
+            data.push(0b0101_0101);
+        }
+        Self {
+            data,
+            rate,
+            looping: false,
+            initial_level,
+        }
+    }
+
+    /// Decodes the stream into PCM values (-1.0 to 1.0), one value per bit, playing once.
+    pub fn decode(&self) -> Vec<f32> {
+        let mut decoder = DpcmDecoder::new(self);
+        let mut result = Vec::with_capacity(self.data.len() * 8);
+        while decoder.step(self) {
+            result.push(decoder.output());
+            if decoder.position() == 0 {
+                break;
+            }
+        }
+        result
+    }
+
+    /// Playback frequency in Hertz, according to the rate index.
+    pub fn frequency(&self) -> f32 {
+        rate_frequency(self.rate)
+    }
+}
+
+/// Playback state of a [DpcmSample]: the current output level and bit position.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct DpcmDecoder {
+    level: u8,
+    position: usize,
+}
+
+impl DpcmDecoder {
+    /// A decoder ready to play the sample from the beginning.
+    pub fn new(sample: &DpcmSample) -> Self {
+        Self {
+            level: sample.initial_level & 127,
+            position: 0,
+        }
+    }
+
+    /// Current 7 bit output level.
+    pub fn level(&self) -> u8 {
+        self.level
+    }
+
+    /// Current output level as a value from -1.0 to 1.0.
+    pub fn output(&self) -> f32 {
+        (self.level as f32 / 63.5) - 1.0
+    }
+
+    /// Index of the next bit to be played.
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    /// Plays the next bit. Returns false if the sample ended and isn't looping,
+    /// in which case the level is held.
+    pub fn step(&mut self, sample: &DpcmSample) -> bool {
+        let bit_count = sample.data.len() * 8;
+        if self.position >= bit_count {
+            if !sample.looping || bit_count == 0 {
+                return false;
+            }
+            self.position = 0;
+        }
+        let up = (sample.data[self.position / 8] >> (self.position % 8)) & 1 == 1;
+        self.level = step_level(self.level, up);
+        self.position += 1;
+        if self.position >= bit_count && sample.looping {
+            self.position = 0;
+        }
+        true
+    }
+}
+
+// Moves the level by two steps, ignoring changes that would overflow the 7 bit range.
+fn step_level(level: u8, up: bool) -> u8 {
+    if up {
+        if level <= 125 {
+            level + 2
+        } else {
+            level
+        }
+    } else if level >= 2 {
+        level - 2
+    } else {
+        level
+    }
+}
+
+fn to_level(value: f32) -> u8 {
+    ((value.clamp(-1.0, 1.0) + 1.0) * 63.5) as u8
+}
+
+#[test]
+fn dpcm_round_trip() {
+    // Slow square wave, the decoded stream ramps towards each half at 2 steps per bit.
+    let rate = 15;
+    let frequency = rate_frequency(rate);
+    let pcm: Vec<f32> = (0..2000)
+        .map(|i| if (i / 500) % 2 == 0 { 0.5 } else { -0.5 })
+        .collect();
+    let sample = DpcmSample::encode(&pcm, frequency, rate);
+    assert_eq!(sample.data.len() % 16, 1);
+    assert_eq!(sample.initial_level, to_level(0.5));
+
+    let decoded = sample.decode();
+    assert_eq!(decoded.len(), sample.data.len() * 8);
+    // Holds the level around the target, then slews down at 2 levels per bit.
+    assert!((decoded[400] - 0.5).abs() < 0.05);
+    assert!((decoded[700] - -0.5).abs() < 0.05);
+    let slew = decoded[501] - decoded[502];
+    assert!((slew - (2.0 / 63.5)).abs() < 0.001);
+
+    // Looping continues from the current level instead of resetting it.
+    let looping = DpcmSample {
+        data: Vec::from([0xFF]),
+        looping: true,
+        initial_level: 0,
+        ..Default::default()
+    };
+    let mut decoder = DpcmDecoder::new(&looping);
+    for _ in 0..16 {
+        assert!(decoder.step(&looping));
+    }
+    assert_eq!(decoder.level(), 32);
+}
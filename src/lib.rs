@@ -5,10 +5,24 @@
 extern crate alloc;
 pub(crate) use alloc::vec::Vec;
 
+pub mod adpcm;
+
+pub mod binary;
+
+pub mod dpcm;
+
 pub mod math;
 
+pub mod midi;
+
+pub mod mml;
+
 pub mod presets;
 
 pub mod prelude;
 
 pub mod rng;
+
+pub mod sequencer;
+
+pub mod wav;
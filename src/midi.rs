@@ -0,0 +1,126 @@
+//! MIDI support: Standard MIDI File playback and live MIDI input, with MIDI channels and
+//! programs mapped to chip channels through a voice allocator.
+
+mod dispatcher;
+pub use dispatcher::*;
+
+mod file;
+pub use file::*;
+
+mod input;
+pub use input::*;
+
+mod instruments;
+pub use instruments::*;
+
+mod player;
+pub use player::*;
+
+/// A MIDI channel message. Channels are zero based, i.e. the General MIDI
+/// percussion channel is 9.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MidiMessage {
+    NoteOff {
+        channel: u8,
+        note: u8,
+        velocity: u8,
+    },
+    NoteOn {
+        channel: u8,
+        note: u8,
+        velocity: u8,
+    },
+    PolyPressure {
+        channel: u8,
+        note: u8,
+        pressure: u8,
+    },
+    ControlChange {
+        channel: u8,
+        controller: u8,
+        value: u8,
+    },
+    ProgramChange {
+        channel: u8,
+        program: u8,
+    },
+    ChannelPressure {
+        channel: u8,
+        pressure: u8,
+    },
+    /// Pitch bend from -8192 to 8191, where zero means no bend.
+    PitchBend {
+        channel: u8,
+        value: i16,
+    },
+}
+
+impl MidiMessage {
+    /// Creates a message from a status byte and its data bytes. Returns None if the status
+    /// byte isn't a channel message, or if there aren't enough data bytes.
+    pub fn from_bytes(status: u8, data: &[u8]) -> Option<Self> {
+        if data.len() < Self::data_len(status)? {
+            return None;
+        }
+        let channel = status & 0x0F;
+        let a = data[0] & 0x7F;
+        let b = data.get(1).map_or(0, |b| b & 0x7F);
+        Some(match status & 0xF0 {
+            0x80 => Self::NoteOff {
+                channel,
+                note: a,
+                velocity: b,
+            },
+            0x90 => Self::NoteOn {
+                channel,
+                note: a,
+                velocity: b,
+            },
+            0xA0 => Self::PolyPressure {
+                channel,
+                note: a,
+                pressure: b,
+            },
+            0xB0 => Self::ControlChange {
+                channel,
+                controller: a,
+                value: b,
+            },
+            0xC0 => Self::ProgramChange {
+                channel,
+                program: a,
+            },
+            0xD0 => Self::ChannelPressure {
+                channel,
+                pressure: a,
+            },
+            _ => Self::PitchBend {
+                channel,
+                value: (((b as i16) << 7) | a as i16) - 8192,
+            },
+        })
+    }
+
+    /// How many data bytes follow a channel message status byte.
+    /// Returns None if the status byte isn't a channel message.
+    pub fn data_len(status: u8) -> Option<usize> {
+        match status & 0xF0 {
+            0x80 | 0x90 | 0xA0 | 0xB0 | 0xE0 => Some(2),
+            0xC0 | 0xD0 => Some(1),
+            _ => None,
+        }
+    }
+
+    /// The message's MIDI channel.
+    pub fn channel(&self) -> u8 {
+        match *self {
+            Self::NoteOff { channel, .. }
+            | Self::NoteOn { channel, .. }
+            | Self::PolyPressure { channel, .. }
+            | Self::ControlChange { channel, .. }
+            | Self::ProgramChange { channel, .. }
+            | Self::ChannelPressure { channel, .. }
+            | Self::PitchBend { channel, .. } => channel,
+        }
+    }
+}
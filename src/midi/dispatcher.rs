@@ -0,0 +1,436 @@
+use super::{MidiInstrumentMap, MidiMessage};
+use crate::{prelude::*, Vec};
+
+// Controller numbers
+const CC_MODULATION: u8 = 1;
+const CC_VOLUME: u8 = 7;
+const CC_PAN: u8 = 10;
+const CC_SUSTAIN: u8 = 64;
+const CC_ALL_SOUND_OFF: u8 = 120;
+const CC_RESET_CONTROLLERS: u8 = 121;
+const CC_ALL_NOTES_OFF: u8 = 123;
+
+/// Which voice is replaced when a new note arrives and every channel is busy.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum VoiceStealing {
+    /// Steals the voice that started playing first.
+    #[default]
+    Oldest,
+    /// Steals the voice with the lowest velocity and channel volume.
+    Quietest,
+    /// A note that is already playing on the same MIDI channel is always retriggered on its
+    /// own voice, even if other channels are free. Otherwise behaves like "Oldest".
+    SameNote,
+}
+
+/// Applies MIDI messages to a [SoundChip], allocating chip channels ("voices") to notes.
+/// A note will use a channel with the same specs as its instrument if possible, otherwise an
+/// idle channel will be reconfigured with the instrument's specs. Channels in a [ChipGroup]
+/// are never reconfigured, and are only used with their own specs as a last resort.
+#[derive(Debug, Clone)]
+pub struct MidiDispatcher {
+    /// Instruments used for each MIDI channel and program.
+    pub instruments: MidiInstrumentMap,
+    /// Voice stealing strategy.
+    pub stealing: VoiceStealing,
+    /// Pitch bend range in semitones, up and down.
+    pub bend_range: f32,
+    /// Vibratto applied by the modulation wheel (CC1), where the amplitude is scaled by the
+    /// wheel's position.
+    pub modulation: Vibratto,
+    channels: [MidiChannelState; 16],
+    voices: Vec<Option<Voice>>,
+    age: u64,
+}
+
+// Controller state for a single MIDI channel.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct MidiChannelState {
+    program: u8,
+    volume: u8,
+    pan: u8,
+    modulation: u8,
+    bend: i16,
+    sustain: bool,
+}
+
+impl Default for MidiChannelState {
+    fn default() -> Self {
+        Self {
+            program: 0,
+            volume: 100,
+            pan: 64,
+            modulation: 0,
+            bend: 0,
+            sustain: false,
+        }
+    }
+}
+
+// A note currently assigned to a chip channel.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Voice {
+    midi_channel: u8,
+    note: u8,
+    // Instrument volume multiplied by velocity
+    volume: f32,
+    held: bool,
+    sustained: bool,
+    age: u64,
+}
+
+impl Voice {
+    // A voice is busy while its key is held, or while the sustain pedal keeps it sounding.
+    fn is_busy(&self) -> bool {
+        self.held || self.sustained
+    }
+}
+
+impl Default for MidiDispatcher {
+    fn default() -> Self {
+        Self {
+            instruments: MidiInstrumentMap::default(),
+            stealing: VoiceStealing::default(),
+            bend_range: 2.0,
+            modulation: Vibratto {
+                steps: None,
+                amplitude: 1.0 / 12.0,
+                frequency: 6.0,
+            },
+            channels: [MidiChannelState::default(); 16],
+            voices: Vec::new(),
+            age: 0,
+        }
+    }
+}
+
+impl MidiDispatcher {
+    /// Creates a dispatcher with an instrument map.
+    pub fn new(instruments: MidiInstrumentMap) -> Self {
+        Self {
+            instruments,
+            ..Default::default()
+        }
+    }
+
+    /// The current program of a MIDI channel.
+    pub fn program(&self, midi_channel: u8) -> u8 {
+        self.channels[(midi_channel & 0x0F) as usize].program
+    }
+
+    /// Forgets all voices, programs and controllers. Does not stop the chip's channels.
+    pub fn reset(&mut self) {
+        self.channels = [MidiChannelState::default(); 16];
+        self.voices.clear();
+        self.age = 0;
+    }
+
+    /// Applies a single message to the chip.
+    pub fn dispatch(&mut self, chip: &mut SoundChip, message: MidiMessage) {
+        self.voices.resize(chip.channels.len(), None);
+        let midi_channel = message.channel() & 0x0F;
+        let state = &mut self.channels[midi_channel as usize];
+        match message {
+            MidiMessage::NoteOn { note, velocity, .. } if velocity > 0 => {
+                self.note_on(chip, midi_channel, note, velocity)
+            }
+            MidiMessage::NoteOn { note, .. } | MidiMessage::NoteOff { note, .. } => {
+                self.note_off(chip, midi_channel, note)
+            }
+            MidiMessage::ProgramChange { program, .. } => state.program = program,
+            MidiMessage::PitchBend { value, .. } => {
+                state.bend = value;
+                self.update_voices(chip, midi_channel);
+            }
+            MidiMessage::ControlChange {
+                controller, value, ..
+            } => match controller {
+                CC_MODULATION => {
+                    state.modulation = value;
+                    self.update_voices(chip, midi_channel);
+                }
+                CC_VOLUME => {
+                    state.volume = value;
+                    self.update_voices(chip, midi_channel);
+                }
+                CC_PAN => {
+                    state.pan = value;
+                    self.update_voices(chip, midi_channel);
+                }
+                CC_SUSTAIN => {
+                    state.sustain = value >= 64;
+                    if !state.sustain {
+                        self.release_sustained(chip, midi_channel);
+                    }
+                }
+                CC_RESET_CONTROLLERS => {
+                    *state = MidiChannelState {
+                        program: state.program,
+                        ..Default::default()
+                    };
+                    self.release_sustained(chip, midi_channel);
+                    self.update_voices(chip, midi_channel);
+                }
+                CC_ALL_NOTES_OFF => self.all_notes_off(chip, midi_channel, false),
+                CC_ALL_SOUND_OFF => self.all_notes_off(chip, midi_channel, true),
+                _ => {}
+            },
+            _ => {}
+        }
+    }
+
+    fn note_on(&mut self, chip: &mut SoundChip, midi_channel: u8, note: u8, velocity: u8) {
+        let program = self.program(midi_channel);
+        let Some(instrument) = self.instruments.get(midi_channel, program) else {
+            return;
+        };
+        let Some(index) = self.allocate(chip, &instrument.specs, midi_channel, note) else {
+            return;
+        };
+        let channel = &mut chip.channels[index];
+        if channel.group().is_none() && channel.specs() != &instrument.specs {
+            channel.set_specs(instrument.specs.clone());
+        }
+        channel.set_sound(&instrument.sound);
+        self.age += 1;
+        let voice = Voice {
+            midi_channel,
+            note,
+            volume: instrument.sound.volume * (velocity as f32 / 127.0),
+            held: true,
+            sustained: false,
+            age: self.age,
+        };
+        self.voices[index] = Some(voice);
+        self.apply(channel, &voice);
+        channel.play();
+    }
+
+    fn note_off(&mut self, chip: &mut SoundChip, midi_channel: u8, note: u8) {
+        let sustain = self.channels[midi_channel as usize].sustain;
+        for (index, slot) in self.voices.iter_mut().enumerate() {
+            let Some(voice) = slot else {
+                continue;
+            };
+            if voice.held && voice.midi_channel == midi_channel && voice.note == note {
+                voice.held = false;
+                if sustain {
+                    voice.sustained = true;
+                } else {
+                    chip.channels[index].release();
+                }
+            }
+        }
+    }
+
+    fn release_sustained(&mut self, chip: &mut SoundChip, midi_channel: u8) {
+        for (index, slot) in self.voices.iter_mut().enumerate() {
+            let Some(voice) = slot else {
+                continue;
+            };
+            if voice.sustained && voice.midi_channel == midi_channel {
+                voice.sustained = false;
+                chip.channels[index].release();
+            }
+        }
+    }
+
+    fn all_notes_off(&mut self, chip: &mut SoundChip, midi_channel: u8, stop: bool) {
+        let sustain = self.channels[midi_channel as usize].sustain;
+        for (index, slot) in self.voices.iter_mut().enumerate() {
+            if slot.is_some_and(|voice| voice.midi_channel == midi_channel) {
+                if stop {
+                    chip.channels[index].stop();
+                    *slot = None;
+                } else if let Some(voice) = slot.as_mut().filter(|voice| voice.held) {
+                    // Same as a note off for every note, so the sustain pedal still holds them.
+                    voice.held = false;
+                    if sustain {
+                        voice.sustained = true;
+                    } else {
+                        chip.channels[index].release();
+                    }
+                }
+            }
+        }
+    }
+
+    // Re-applies controllers to every busy voice of a MIDI channel.
+    fn update_voices(&self, chip: &mut SoundChip, midi_channel: u8) {
+        for (index, slot) in self.voices.iter().enumerate() {
+            if let Some(voice) = slot {
+                if voice.is_busy() && voice.midi_channel == midi_channel {
+                    self.apply(&mut chip.channels[index], voice);
+                }
+            }
+        }
+    }
+
+    // Applies pitch, volume, pan and modulation from the voice and its MIDI channel.
+    fn apply(&self, channel: &mut Channel, voice: &Voice) {
+        let state = &self.channels[voice.midi_channel as usize];
+        let bend = (state.bend as f32 / 8192.0) * self.bend_range;
+        channel.set_midi_note(voice.note as f32 + bend);
+        channel.set_volume(voice.volume * (state.volume as f32 / 127.0));
+        channel.set_pan(((state.pan as f32 - 64.0) / 63.0).clamp(-1.0, 1.0));
+        if state.modulation > 0 {
+            channel.set_vibratto(Some(Vibratto {
+                amplitude: self.modulation.amplitude * (state.modulation as f32 / 127.0),
+                ..self.modulation
+            }));
+        } else {
+            channel.set_vibratto(self.instruments_vibratto(voice));
+        }
+    }
+
+    // The instrument's own vibratto, used when the modulation wheel is at zero.
+    fn instruments_vibratto(&self, voice: &Voice) -> Option<Vibratto> {
+        let program = self.program(voice.midi_channel);
+        self.instruments
+            .get(voice.midi_channel, program)
+            .and_then(|instrument| instrument.sound.vibratto)
+    }
+
+    // Finds the best channel for a new note. Channels without busy voices come first,
+    // preferring the ones with the same specs, then the ones that can be reconfigured, then
+    // the oldest released notes. If every channel is busy a voice is stolen according to the
+    // stealing strategy, with the same preferences and voices only kept by the sustain pedal.
+    fn allocate(
+        &self,
+        chip: &SoundChip,
+        specs: &SpecsChip,
+        midi_channel: u8,
+        note: u8,
+    ) -> Option<usize> {
+        let indices = 0..chip.channels.len();
+        if self.stealing == VoiceStealing::SameNote {
+            let same_note = indices.clone().find(|index| {
+                self.voices[*index].is_some_and(|voice| {
+                    voice.is_busy() && voice.midi_channel == midi_channel && voice.note == note
+                })
+            });
+            if same_note.is_some() {
+                return same_note;
+            }
+        }
+        // Grouped channels share resources with the rest of their chip, so they keep their specs.
+        let rank = |index: usize| {
+            let channel = &chip.channels[index];
+            if channel.specs() == specs {
+                0
+            } else if channel.group().is_none() {
+                1
+            } else {
+                2
+            }
+        };
+        let free = indices
+            .clone()
+            .filter(|index| !self.voices[*index].is_some_and(|voice| voice.is_busy()))
+            .min_by_key(|index| {
+                let age = self.voices[*index].map_or(0, |voice| voice.age);
+                (rank(*index), age)
+            });
+        if free.is_some() {
+            return free;
+        }
+        match self.stealing {
+            VoiceStealing::Oldest | VoiceStealing::SameNote => indices.min_by_key(|index| {
+                let (held, age) = self.voices[*index].map_or((false, 0), |v| (v.held, v.age));
+                (rank(*index), held, age)
+            }),
+            VoiceStealing::Quietest => indices.min_by(|a, b| {
+                let loudness = |index: usize| {
+                    self.voices[index].map_or(0.0, |voice| {
+                        voice.volume * self.channels[voice.midi_channel as usize].volume as f32
+                    })
+                };
+                let held = |index: usize| self.voices[index].is_some_and(|voice| voice.held);
+                (rank(*a), held(*a))
+                    .cmp(&(rank(*b), held(*b)))
+                    .then(loudness(*a).total_cmp(&loudness(*b)))
+            }),
+        }
+    }
+}
+
+#[test]
+fn grouped_channels_keep_their_specs() {
+    use crate::presets::*;
+    let mut chip = SoundChip::new_msx_scc(44100);
+    let mut map = MidiInstrumentMap::new();
+    map.insert(
+        None,
+        None,
+        super::MidiInstrument {
+            sound: Sound::default(),
+            specs: SPEC_CHIP_PSG,
+        },
+    );
+    let mut dispatcher = MidiDispatcher::new(map);
+    let note_on = |note| MidiMessage::NoteOn {
+        channel: 0,
+        note,
+        velocity: 100,
+    };
+    // The two plain PSG channels first, then an SCC channel reconfigured as a PSG.
+    for note in [60, 62, 64] {
+        dispatcher.dispatch(&mut chip, note_on(note));
+    }
+    assert!((1..4).all(|index| chip.channels[index].is_playing()));
+    assert_eq!(chip.channels[3].specs(), &SPEC_CHIP_PSG);
+    assert!(!chip.channels[0].is_playing());
+    assert_eq!(chip.channels[0].specs(), &SPEC_CHIP_PSG_NOISE);
+
+    // Every channel is grouped, so the note plays with the channel's own specs.
+    let mut nes = SoundChip::new_nes(44100);
+    dispatcher.reset();
+    dispatcher.dispatch(&mut nes, note_on(60));
+    assert!(nes.channels[0].is_playing());
+    assert_eq!(nes.channels[0].specs(), &SPEC_CHIP_NES_SQUARE);
+}
+
+#[test]
+fn default_instruments_play_on_any_chip() {
+    let mut chip = SoundChip::new_msx(44100);
+    let mut dispatcher = MidiDispatcher::default();
+    dispatcher.dispatch(
+        &mut chip,
+        MidiMessage::NoteOn {
+            channel: 0,
+            note: 60,
+            velocity: 100,
+        },
+    );
+    let peak = chip.iter(2000).map(|s| s.left.unsigned_abs()).max();
+    assert!(peak.is_some_and(|peak| peak > 1000));
+}
+
+#[test]
+fn all_notes_off_respects_sustain() {
+    use crate::presets::*;
+    let mut chip = SoundChip::new(44100);
+    chip.add_channel(SPEC_CHIP_NES_SQUARE);
+    let mut dispatcher = MidiDispatcher::default();
+    let control = |controller, value| MidiMessage::ControlChange {
+        channel: 0,
+        controller,
+        value,
+    };
+    dispatcher.dispatch(&mut chip, control(CC_SUSTAIN, 127));
+    dispatcher.dispatch(
+        &mut chip,
+        MidiMessage::NoteOn {
+            channel: 0,
+            note: 60,
+            velocity: 100,
+        },
+    );
+    dispatcher.dispatch(&mut chip, control(CC_ALL_NOTES_OFF, 0));
+    let voice = dispatcher.voices[0].unwrap();
+    assert!(!voice.held && voice.sustained);
+
+    // Releasing the pedal releases the note.
+    dispatcher.dispatch(&mut chip, control(CC_SUSTAIN, 0));
+    assert!(!dispatcher.voices[0].unwrap().is_busy());
+}
@@ -0,0 +1,270 @@
+use super::MidiMessage;
+use crate::{prelude::ChipError, Vec};
+
+// Default tempo, in microseconds per quarter note (120 BPM).
+const DEFAULT_TEMPO: u32 = 500_000;
+
+/// A parsed Standard MIDI File (format 0, 1 or 2).
+#[derive(Debug, Clone, PartialEq)]
+pub struct MidiFile {
+    /// 0 is a single track, 1 is multiple simultaneous tracks, 2 is multiple independent tracks.
+    pub format: u16,
+    /// Raw time division from the header. If the top bit is clear, it's ticks per quarter note.
+    /// Otherwise it's SMPTE frames per second (negative, upper byte) and ticks per frame.
+    pub division: u16,
+    /// Events for each track, in the order they appear in the file.
+    pub tracks: Vec<Vec<MidiEvent>>,
+}
+
+/// A single track event with its delta time in ticks.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MidiEvent {
+    pub delta: u32,
+    pub kind: MidiEventKind,
+}
+
+/// The relevant kinds of track events. Other meta and system exclusive events are skipped
+/// while parsing, but their delta times are preserved.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MidiEventKind {
+    Message(MidiMessage),
+    /// Microseconds per quarter note.
+    Tempo(u32),
+    /// Meta and system exclusive events with no effect on playback.
+    Other,
+}
+
+/// A message with its absolute time in seconds.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MidiTimedMessage {
+    pub time: f64,
+    pub message: MidiMessage,
+}
+
+impl MidiFile {
+    /// Parses a Standard MIDI File. Returns [ChipError::InvalidMidi] if the data is malformed.
+    pub fn parse(data: &[u8]) -> Result<Self, ChipError> {
+        let mut reader = Reader { data, head: 0 };
+        if reader.bytes(4)? != b"MThd" {
+            return Err(ChipError::InvalidMidi);
+        }
+        let header_len = reader.u32()? as usize;
+        if header_len < 6 {
+            return Err(ChipError::InvalidMidi);
+        }
+        let format = reader.u16()?;
+        let track_count = reader.u16()?;
+        let division = reader.u16()?;
+        // SMPTE divisions only allow the standard frame rates
+        if division & 0x8000 != 0 && ![-24, -25, -29, -30].contains(&((division >> 8) as i8)) {
+            return Err(ChipError::InvalidMidi);
+        }
+        reader.bytes(header_len - 6)?;
+
+        let mut tracks = Vec::new();
+        while tracks.len() < track_count as usize {
+            let id = reader.bytes(4)?;
+            let len = reader.u32()? as usize;
+            let chunk = reader.bytes(len)?;
+            // Unknown chunks must be ignored
+            if id == b"MTrk" {
+                tracks.push(parse_track(chunk)?);
+            }
+        }
+        Ok(Self {
+            format,
+            division,
+            tracks,
+        })
+    }
+
+    /// All channel messages from every track, merged and sorted by time in seconds,
+    /// taking tempo changes into account.
+    pub fn timeline(&self) -> Vec<MidiTimedMessage> {
+        // Absolute ticks, track index and event kind.
+        let mut events = Vec::new();
+        for (index, track) in self.tracks.iter().enumerate() {
+            let mut ticks: u64 = 0;
+            for event in track {
+                ticks += event.delta as u64;
+                events.push((ticks, index, event.kind));
+            }
+        }
+        // Stable sort keeps the file order for simultaneous events
+        events.sort_by_key(|(ticks, _, _)| *ticks);
+
+        let mut result = Vec::new();
+        let mut tempo = DEFAULT_TEMPO;
+        let mut last_ticks = 0;
+        let mut time = 0.0;
+        for (ticks, _, kind) in events {
+            time += (ticks - last_ticks) as f64 * self.seconds_per_tick(tempo);
+            last_ticks = ticks;
+            match kind {
+                MidiEventKind::Message(message) => result.push(MidiTimedMessage { time, message }),
+                MidiEventKind::Tempo(value) => tempo = value,
+                MidiEventKind::Other => {}
+            }
+        }
+        result
+    }
+
+    fn seconds_per_tick(&self, tempo: u32) -> f64 {
+        if self.division & 0x8000 != 0 {
+            let fps = ((self.division >> 8) as i8).unsigned_abs() as f64;
+            let ticks_per_frame = (self.division & 0xFF) as f64;
+            1.0 / (fps * ticks_per_frame).max(1.0)
+        } else {
+            tempo as f64 / 1_000_000.0 / self.division.max(1) as f64
+        }
+    }
+}
+
+fn parse_track(data: &[u8]) -> Result<Vec<MidiEvent>, ChipError> {
+    let mut reader = Reader { data, head: 0 };
+    let mut events = Vec::new();
+    let mut running_status: Option<u8> = None;
+    while reader.head < data.len() {
+        let delta = reader.var_len()?;
+        let first = reader.u8()?;
+        let kind = match first {
+            0xFF => {
+                let meta = reader.u8()?;
+                let len = reader.var_len()? as usize;
+                let payload = reader.bytes(len)?;
+                match meta {
+                    0x2F => {
+                        events.push(MidiEvent {
+                            delta,
+                            kind: MidiEventKind::Other,
+                        });
+                        break;
+                    }
+                    0x51 if len == 3 => MidiEventKind::Tempo(
+                        ((payload[0] as u32) << 16)
+                            | ((payload[1] as u32) << 8)
+                            | payload[2] as u32,
+                    ),
+                    _ => MidiEventKind::Other,
+                }
+            }
+            0xF0 | 0xF7 => {
+                let len = reader.var_len()? as usize;
+                reader.bytes(len)?;
+                MidiEventKind::Other
+            }
+            _ => {
+                // Data bytes without a status byte use the previous status
+                let (status, data) = if first & 0x80 != 0 {
+                    running_status = Some(first);
+                    let len = MidiMessage::data_len(first).ok_or(ChipError::InvalidMidi)?;
+                    (first, reader.bytes(len)?)
+                } else {
+                    let status = running_status.ok_or(ChipError::InvalidMidi)?;
+                    let len = MidiMessage::data_len(status).ok_or(ChipError::InvalidMidi)?;
+                    reader.head -= 1;
+                    (status, reader.bytes(len)?)
+                };
+                MidiEventKind::Message(
+                    MidiMessage::from_bytes(status, data).ok_or(ChipError::InvalidMidi)?,
+                )
+            }
+        };
+        events.push(MidiEvent { delta, kind });
+    }
+    Ok(events)
+}
+
+// Big endian byte reader that fails on unexpected end of data.
+struct Reader<'a> {
+    data: &'a [u8],
+    head: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn bytes(&mut self, len: usize) -> Result<&'a [u8], ChipError> {
+        let end = self.head.checked_add(len).ok_or(ChipError::InvalidMidi)?;
+        let result = self.data.get(self.head..end).ok_or(ChipError::InvalidMidi)?;
+        self.head = end;
+        Ok(result)
+    }
+
+    fn u8(&mut self) -> Result<u8, ChipError> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, ChipError> {
+        let bytes = self.bytes(2)?;
+        Ok(u16::from_be_bytes([bytes[0], bytes[1]]))
+    }
+
+    fn u32(&mut self) -> Result<u32, ChipError> {
+        let bytes = self.bytes(4)?;
+        Ok(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    // Variable length quantity, up to 4 bytes.
+    fn var_len(&mut self) -> Result<u32, ChipError> {
+        let mut result: u32 = 0;
+        for _ in 0..4 {
+            let byte = self.u8()?;
+            result = (result << 7) | (byte & 0x7F) as u32;
+            if byte & 0x80 == 0 {
+                return Ok(result);
+            }
+        }
+        Err(ChipError::InvalidMidi)
+    }
+}
+
+#[test]
+fn midi_file_parsing() {
+    #[rustfmt::skip]
+    let data = [
+        b'M', b'T', b'h', b'd', 0, 0, 0, 6, 0, 1, 0, 2, 0, 96,
+        // Tempo track: 60 BPM, then 120 BPM after one quarter note.
+        b'M', b'T', b'r', b'k', 0, 0, 0, 18,
+        0x00, 0xFF, 0x51, 3, 0x0F, 0x42, 0x40,
+        0x60, 0xFF, 0x51, 3, 0x07, 0xA1, 0x20,
+        0x00, 0xFF, 0x2F, 0,
+        // Note track, using running status and a two byte delta time.
+        b'M', b'T', b'r', b'k', 0, 0, 0, 19,
+        0x00, 0x90, 60, 100,
+        0x60, 60, 0,
+        0x81, 0x40, 0x80, 62, 64,
+        0x00, 0xC0, 0x05,
+        0x00, 0xFF, 0x2F, 0,
+    ];
+    let file = MidiFile::parse(&data).unwrap();
+    assert_eq!(file.format, 1);
+    assert_eq!(file.tracks.len(), 2);
+
+    let timeline = file.timeline();
+    let times: Vec<f64> = timeline.iter().map(|m| m.time).collect();
+    assert_eq!(times.as_slice(), &[0.0, 1.0, 2.0, 2.0]);
+    assert_eq!(
+        timeline[1].message,
+        MidiMessage::NoteOn {
+            channel: 0,
+            note: 60,
+            velocity: 0
+        }
+    );
+    assert_eq!(
+        timeline[3].message,
+        MidiMessage::ProgramChange {
+            channel: 0,
+            program: 5
+        }
+    );
+
+    assert_eq!(MidiFile::parse(&data[..20]), Err(ChipError::InvalidMidi));
+
+    // SMPTE divisions: 25 fps with 40 ticks per frame, and an invalid frame rate.
+    let mut smpte = data;
+    smpte[12..14].copy_from_slice(&[0xE7, 40]);
+    let file = MidiFile::parse(&smpte).unwrap();
+    assert_eq!(file.timeline()[1].time, 96.0 / 1000.0);
+    smpte[12..14].copy_from_slice(&[0x80, 0x18]);
+    assert_eq!(MidiFile::parse(&smpte), Err(ChipError::InvalidMidi));
+}
@@ -0,0 +1,108 @@
+use super::{MidiDispatcher, MidiInstrumentMap, MidiMessage};
+use crate::prelude::*;
+
+/// Accepts a live stream of raw MIDI bytes (i.e. from a serial port or a USB MIDI device)
+/// and applies complete messages to a [SoundChip] as they arrive. Handles running status,
+/// and skips system exclusive and real time messages.
+#[derive(Debug, Clone, Default)]
+pub struct MidiInput {
+    /// Maps MIDI messages to the chip's channels.
+    pub dispatcher: MidiDispatcher,
+    status: Option<u8>,
+    data: [u8; 2],
+    data_len: usize,
+    sysex: bool,
+}
+
+impl MidiInput {
+    /// Creates an input with an instrument map.
+    pub fn new(instruments: MidiInstrumentMap) -> Self {
+        Self {
+            dispatcher: MidiDispatcher::new(instruments),
+            ..Default::default()
+        }
+    }
+
+    /// Parses bytes and dispatches every complete message. Incomplete messages are kept
+    /// until the next call.
+    pub fn write(&mut self, chip: &mut SoundChip, bytes: &[u8]) {
+        for byte in bytes {
+            if let Some(message) = self.parse_byte(*byte) {
+                self.dispatcher.dispatch(chip, message);
+            }
+        }
+    }
+
+    /// Dispatches a message that was already parsed.
+    pub fn write_message(&mut self, chip: &mut SoundChip, message: MidiMessage) {
+        self.dispatcher.dispatch(chip, message);
+    }
+
+    // Returns a message when its last data byte arrives.
+    fn parse_byte(&mut self, byte: u8) -> Option<MidiMessage> {
+        match byte {
+            // Real time messages can appear anywhere, even between data bytes
+            0xF8..=0xFF => None,
+            0xF0 => {
+                self.sysex = true;
+                self.status = None;
+                None
+            }
+            // End of exclusive, and system common messages cancel running status
+            0xF1..=0xF7 => {
+                self.sysex = false;
+                self.status = None;
+                None
+            }
+            0x80..=0xEF => {
+                self.sysex = false;
+                self.status = Some(byte);
+                self.data_len = 0;
+                None
+            }
+            _ => {
+                if self.sysex {
+                    return None;
+                }
+                let status = self.status?;
+                let len = MidiMessage::data_len(status)?;
+                self.data[self.data_len] = byte;
+                self.data_len += 1;
+                if self.data_len < len {
+                    return None;
+                }
+                // Keep the status for running status
+                self.data_len = 0;
+                MidiMessage::from_bytes(status, &self.data[..len])
+            }
+        }
+    }
+}
+
+#[test]
+fn midi_input_stream() {
+    use crate::presets::*;
+    let mut chip = SoundChip::new(44100);
+    chip.add_channel(SPEC_CHIP_NES_SQUARE);
+    chip.add_channel(SPEC_CHIP_NES_SQUARE);
+    let mut input = MidiInput::default();
+
+    // Note on with running status and a real time byte in the middle.
+    input.write(&mut chip, &[0x90, 60, 100, 64, 0xF8, 100]);
+    assert!(chip.channels[0].is_playing());
+    assert!(chip.channels[1].is_playing());
+
+    // Pitch bend of one whole tone up, in two separate writes.
+    input.write(&mut chip, &[0xE0, 0x7F]);
+    input.write(&mut chip, &[0x7F]);
+    let pitch = crate::math::note_to_frequency(62.0);
+    assert!((chip.channels[0].pitch() - pitch).abs() < 1.0);
+
+    // A voice kept by the sustain pedal is stolen before a held one.
+    input.write(&mut chip, &[0xB0, 64, 127, 0x80, 60, 0]);
+    input.write(&mut chip, &[0x90, 67, 100]);
+    let stolen = crate::math::note_to_frequency(69.0);
+    let held = crate::math::note_to_frequency(66.0);
+    assert!((chip.channels[0].pitch() - stolen).abs() < 1.0);
+    assert!((chip.channels[1].pitch() - held).abs() < 1.0);
+}
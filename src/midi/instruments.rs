@@ -0,0 +1,106 @@
+use crate::{prelude::*, presets::*, Vec};
+use core::ops::RangeInclusive;
+
+/// A Sound and the chip specs it should be played with.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MidiInstrument {
+    pub sound: Sound,
+    pub specs: SpecsChip,
+}
+
+/// Maps MIDI channels and program numbers to instruments.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MidiInstrumentMap {
+    entries: Vec<MidiInstrumentEntry>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct MidiInstrumentEntry {
+    channel: Option<u8>,
+    programs: Option<RangeInclusive<u8>>,
+    instrument: MidiInstrument,
+}
+
+impl Default for MidiInstrumentMap {
+    /// Returns an NES-like map: square waves for most programs, triangle for bass programs
+    /// and noise for the percussion channel.
+    fn default() -> Self {
+        let mut result = Self::new();
+        result.insert(
+            None,
+            None,
+            MidiInstrument {
+                sound: Sound {
+                    volume_env: Some(Envelope::from(KNOTS_VOL_PIANO).scale_time(2.0)),
+                    ..Default::default()
+                },
+                specs: SPEC_CHIP_NES_SQUARE,
+            },
+        );
+        result.insert(
+            None,
+            Some(32..=39),
+            MidiInstrument {
+                sound: Sound {
+                    volume_env: None,
+                    ..Default::default()
+                },
+                specs: SPEC_CHIP_NES_TRIANGLE,
+            },
+        );
+        result.insert(
+            Some(9),
+            None,
+            MidiInstrument {
+                sound: Sound {
+                    volume_env: Some(Envelope::from(KNOTS_VOL_DOWN).scale_time(0.25)),
+                    noise_env: Some(Envelope::from(KNOTS_FLAT_ONE)),
+                    ..Default::default()
+                },
+                specs: SPEC_CHIP_NES_NOISE,
+            },
+        );
+        result
+    }
+}
+
+impl MidiInstrumentMap {
+    /// Creates an empty map. Notes without a matching instrument are ignored.
+    pub fn new() -> Self {
+        Self {
+            entries: Vec::new(),
+        }
+    }
+
+    /// Adds an instrument for a MIDI channel (or any channel, if None) and a range of
+    /// programs (or any program, if None). Entries added later take priority.
+    pub fn insert(
+        &mut self,
+        channel: Option<u8>,
+        programs: Option<RangeInclusive<u8>>,
+        instrument: MidiInstrument,
+    ) {
+        self.entries.push(MidiInstrumentEntry {
+            channel,
+            programs,
+            instrument,
+        });
+    }
+
+    /// Removes all entries.
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    /// Finds the instrument for a MIDI channel and program.
+    pub fn get(&self, channel: u8, program: u8) -> Option<&MidiInstrument> {
+        self.entries
+            .iter()
+            .rev()
+            .find(|entry| {
+                entry.channel.is_none_or(|c| c == channel)
+                    && entry.programs.as_ref().is_none_or(|p| p.contains(&program))
+            })
+            .map(|entry| &entry.instrument)
+    }
+}
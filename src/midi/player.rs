@@ -0,0 +1,110 @@
+use super::{MidiDispatcher, MidiFile, MidiInstrumentMap, MidiTimedMessage};
+use crate::{prelude::*, Vec};
+
+/// Owns a [SoundChip] and plays a [MidiFile] on it. Messages are applied with
+/// sample accuracy while rendering.
+pub struct MidiPlayer {
+    /// The SoundChip driven by this player.
+    pub chip: SoundChip,
+    /// Maps MIDI messages to the chip's channels.
+    pub dispatcher: MidiDispatcher,
+    timeline: Vec<MidiTimedMessage>,
+    head: usize,
+    time: f64,
+    playing: bool,
+}
+
+impl MidiPlayer {
+    /// Creates a stopped player with the default instrument map.
+    pub fn new(chip: SoundChip, file: &MidiFile) -> Self {
+        Self {
+            chip,
+            dispatcher: MidiDispatcher::new(MidiInstrumentMap::default()),
+            timeline: file.timeline(),
+            head: 0,
+            time: 0.0,
+            playing: false,
+        }
+    }
+
+    /// Replaces the current file, stopping playback.
+    pub fn set_file(&mut self, file: &MidiFile) {
+        self.timeline = file.timeline();
+        self.stop();
+    }
+
+    /// Starts (or resumes) playback from the current position.
+    pub fn play(&mut self) {
+        self.playing = true;
+    }
+
+    /// Stops playback and all channels, and rewinds to the beginning.
+    pub fn stop(&mut self) {
+        self.playing = false;
+        self.head = 0;
+        self.time = 0.0;
+        self.dispatcher.reset();
+        self.chip.channel_stop_all();
+    }
+
+    /// True until every message has been dispatched.
+    pub fn is_playing(&self) -> bool {
+        self.playing
+    }
+
+    /// Playback position in seconds.
+    pub fn time(&self) -> f64 {
+        self.time
+    }
+
+    /// The time of the last message in seconds.
+    pub fn duration(&self) -> f64 {
+        self.timeline.last().map_or(0.0, |message| message.time)
+    }
+
+    /// Renders a given number of samples on demand, advancing playback.
+    pub fn iter(&mut self, sample_count: usize) -> MidiPlayerIter<'_> {
+        MidiPlayerIter {
+            player: self,
+            head: 0,
+            sample_count,
+        }
+    }
+
+    /// Process a single sample, advancing playback and the chip's internal timer.
+    pub fn process_sample(&mut self) -> Sample<i16> {
+        if self.playing {
+            while let Some(timed) = self.timeline.get(self.head) {
+                if timed.time > self.time {
+                    break;
+                }
+                self.dispatcher.dispatch(&mut self.chip, timed.message);
+                self.head += 1;
+            }
+            if self.head >= self.timeline.len() {
+                self.playing = false;
+            }
+            self.time += 1.0 / self.chip.sample_rate as f64;
+        }
+        self.chip.process_sample()
+    }
+}
+
+/// Iterates a specified number of samples. Use [MidiPlayer::iter()] to obtain this.
+pub struct MidiPlayerIter<'a> {
+    player: &'a mut MidiPlayer,
+    head: usize,
+    sample_count: usize,
+}
+
+impl Iterator for MidiPlayerIter<'_> {
+    type Item = Sample<i16>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.head < self.sample_count {
+            self.head += 1;
+            return Some(self.player.process_sample());
+        }
+        None
+    }
+}
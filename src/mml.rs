@@ -0,0 +1,297 @@
+//! Music Macro Language, compatible with the MSX BASIC "PLAY" statement.
+//!
+//! Supported commands (case insensitive, spaces are ignored):
+//! - `A` to `G`: Notes, optionally followed by `+`, `#` (sharp) or `-` (flat), a length and dots.
+//! - `R`: Rest, optionally followed by a length and dots.
+//! - `.`: Each dot adds half of the previous addition to the length, up to 8 dots.
+//! - `N`: Note by number, from 0 to 96 where N36 is O4C. N0 is a rest.
+//! - `O`: Octave, from 1 to 8. Default is 4. `<` and `>` go one octave down or up.
+//! - `L`: Default note length, from 1 (whole note) to 64. Default is 4.
+//! - `T`: Tempo in quarter notes per minute, from 32 to 255. Default is 120.
+//! - `V`: Volume, from 0 to 15. Default is 8. Disables the envelope.
+//! - `S`: Envelope shape, from 0 to 15, using the AY-3-8910 shapes. Enables the envelope.
+//! - `M`: Envelope period, from 1 to 65535. Default is 255.
+//!
+//! Envelopes use the channel group's [HardwareEnvelope](crate::prelude::HardwareEnvelope)
+//! when the channel supports it (i.e. [SoundChip::new_msx()](crate::prelude::SoundChip::new_msx)),
+//! otherwise they are approximated with a volume envelope.
+
+mod player;
+pub use player::*;
+
+use crate::{
+    prelude::{ChipError, Envelope, Interpolation, Knot, LoopKind, Normal},
+    Vec,
+};
+use core::{iter::Peekable, str::CharIndices};
+
+// MSX PSG clock, used to convert the envelope period into seconds.
+const PSG_CLOCK: f32 = 1_789_772.5;
+
+// More dots than this make no audible difference.
+const MAX_DOTS: u32 = 8;
+
+/// A single, fully resolved MML event. Tempo and lengths are already converted to seconds.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MmlEvent {
+    /// Plays a MIDI note (C4 = 60) for a duration in seconds.
+    Note { note: f32, duration: f32 },
+    /// Silence for a duration in seconds.
+    Rest { duration: f32 },
+    /// Sets the volume (0.0 to 1.0) for the next notes, disabling the envelope.
+    Volume(f32),
+    /// Sets the envelope shape and period for the next notes.
+    Envelope { shape: u8, period: u16 },
+}
+
+/// Parses a single channel MML string into a list of events.
+/// Returns [ChipError::InvalidMml] with the character position if the string can't be parsed.
+pub fn parse(source: &str) -> Result<Vec<MmlEvent>, ChipError> {
+    let mut events = Vec::new();
+    let mut octave: i32 = 4;
+    let mut length: u32 = 4;
+    let mut tempo: u32 = 120;
+    let mut shape: u8 = 0;
+    let mut period: u16 = 255;
+    let mut chars = source.char_indices().peekable();
+
+    let duration = |tempo: u32, length: u32, dots: u32| -> f32 {
+        let base = 240.0 / (tempo * length) as f32;
+        // Each dot adds half of the previous addition
+        base * (2.0 - (1.0 / (1 << dots) as f32))
+    };
+
+    while let Some((position, c)) = chars.next() {
+        let error = ChipError::InvalidMml { position };
+        match c.to_ascii_uppercase() {
+            ' ' | '\t' | '\r' | '\n' => {}
+            note @ 'A'..='G' => {
+                let semitone = match note {
+                    'C' => 0,
+                    'D' => 2,
+                    'E' => 4,
+                    'F' => 5,
+                    'G' => 7,
+                    'A' => 9,
+                    _ => 11,
+                };
+                let accidental = match chars.peek() {
+                    Some((_, '+' | '#')) => 1,
+                    Some((_, '-')) => -1,
+                    _ => 0,
+                };
+                if accidental != 0 {
+                    chars.next();
+                }
+                let note_length = parse_number(&mut chars).unwrap_or(length);
+                if !(1..=64).contains(&note_length) {
+                    return Err(error);
+                }
+                let dots = parse_dots(&mut chars).ok_or(error)?;
+                events.push(MmlEvent::Note {
+                    note: (((octave + 1) * 12) + semitone + accidental) as f32,
+                    duration: duration(tempo, note_length, dots),
+                });
+            }
+            'R' => {
+                let rest_length = parse_number(&mut chars).unwrap_or(length);
+                if !(1..=64).contains(&rest_length) {
+                    return Err(error);
+                }
+                let dots = parse_dots(&mut chars).ok_or(error)?;
+                events.push(MmlEvent::Rest {
+                    duration: duration(tempo, rest_length, dots),
+                });
+            }
+            'N' => {
+                let number = parse_number(&mut chars).ok_or(error)?;
+                let dots = parse_dots(&mut chars).ok_or(error)?;
+                let duration = duration(tempo, length, dots);
+                match number {
+                    0 => events.push(MmlEvent::Rest { duration }),
+                    1..=96 => events.push(MmlEvent::Note {
+                        note: (number + 24) as f32,
+                        duration,
+                    }),
+                    _ => return Err(error),
+                }
+            }
+            'O' => {
+                octave = parse_in_range(&mut chars, 1, 8).ok_or(error)? as i32;
+            }
+            '<' => octave = (octave - 1).max(1),
+            '>' => octave = (octave + 1).min(8),
+            'L' => {
+                length = parse_in_range(&mut chars, 1, 64).ok_or(error)?;
+            }
+            'T' => {
+                tempo = parse_in_range(&mut chars, 32, 255).ok_or(error)?;
+            }
+            'V' => {
+                let volume = parse_in_range(&mut chars, 0, 15).ok_or(error)?;
+                events.push(MmlEvent::Volume(volume as f32 / 15.0));
+            }
+            'S' => {
+                shape = parse_in_range(&mut chars, 0, 15).ok_or(error)? as u8;
+                events.push(MmlEvent::Envelope { shape, period });
+            }
+            'M' => {
+                period = parse_in_range(&mut chars, 1, 65535).ok_or(error)? as u16;
+                events.push(MmlEvent::Envelope { shape, period });
+            }
+            _ => return Err(error),
+        }
+    }
+    Ok(events)
+}
+
+/// Generates a volume envelope that mimics one of the 16 AY-3-8910 envelope shapes.
+/// The period uses the same units as the MML "M" command.
+pub fn envelope_from_shape(shape: u8, period: u16) -> Envelope<Normal> {
+    use Interpolation::*;
+    let t = (256.0 * period.max(1) as f32) / PSG_CLOCK;
+    // Used to create an instant transition after the end of a ramp
+    let after = t * 1.001;
+    let knot = |time: f32, value: Normal, interpolation: Interpolation| Knot {
+        time,
+        value,
+        interpolation,
+    };
+    let (knots, loop_kind) = match shape & 0x0F {
+        8 => (
+            Vec::from([
+                knot(0.0, Normal::ONE, Linear),
+                knot(t, Normal::ZERO, Linear),
+            ]),
+            LoopKind::Repeat,
+        ),
+        10 => (
+            Vec::from([
+                knot(0.0, Normal::ONE, Linear),
+                knot(t, Normal::ZERO, Linear),
+                knot(t * 2.0, Normal::ONE, Linear),
+            ]),
+            LoopKind::Repeat,
+        ),
+        11 => (
+            Vec::from([
+                knot(0.0, Normal::ONE, Linear),
+                knot(t, Normal::ZERO, Step),
+                knot(after, Normal::ONE, Step),
+            ]),
+            LoopKind::None,
+        ),
+        12 => (
+            Vec::from([
+                knot(0.0, Normal::ZERO, Linear),
+                knot(t, Normal::ONE, Linear),
+            ]),
+            LoopKind::Repeat,
+        ),
+        13 => (
+            Vec::from([
+                knot(0.0, Normal::ZERO, Linear),
+                knot(t, Normal::ONE, Linear),
+            ]),
+            LoopKind::None,
+        ),
+        14 => (
+            Vec::from([
+                knot(0.0, Normal::ZERO, Linear),
+                knot(t, Normal::ONE, Linear),
+                knot(t * 2.0, Normal::ZERO, Linear),
+            ]),
+            LoopKind::Repeat,
+        ),
+        4..=7 | 15 => (
+            Vec::from([
+                knot(0.0, Normal::ZERO, Linear),
+                knot(t, Normal::ONE, Step),
+                knot(after, Normal::ZERO, Step),
+            ]),
+            LoopKind::None,
+        ),
+        // 0 to 3 and 9
+        _ => (
+            Vec::from([
+                knot(0.0, Normal::ONE, Linear),
+                knot(t, Normal::ZERO, Linear),
+            ]),
+            LoopKind::None,
+        ),
+    };
+    Envelope::from(knots.as_slice()).set_loop(loop_kind)
+}
+
+fn parse_number(chars: &mut Peekable<CharIndices>) -> Option<u32> {
+    let mut result: Option<u32> = None;
+    while let Some((_, c)) = chars.peek() {
+        let Some(digit) = c.to_digit(10) else {
+            break;
+        };
+        result = Some(result.unwrap_or(0).saturating_mul(10).saturating_add(digit));
+        chars.next();
+    }
+    result
+}
+
+fn parse_in_range(chars: &mut Peekable<CharIndices>, min: u32, max: u32) -> Option<u32> {
+    parse_number(chars).filter(|n| *n >= min && *n <= max)
+}
+
+// None if there are more dots than MAX_DOTS.
+fn parse_dots(chars: &mut Peekable<CharIndices>) -> Option<u32> {
+    let mut dots = 0;
+    while let Some((_, '.')) = chars.peek() {
+        dots += 1;
+        chars.next();
+    }
+    (dots <= MAX_DOTS).then_some(dots)
+}
+
+#[test]
+fn mml_parsing() {
+    let events = parse("t120 o4 c d8. r16 e+ >c <<b- n36 v15 s8m100").unwrap();
+    let expected = [
+        MmlEvent::Note {
+            note: 60.0,
+            duration: 0.5,
+        },
+        MmlEvent::Note {
+            note: 62.0,
+            duration: 0.375,
+        },
+        MmlEvent::Rest { duration: 0.125 },
+        MmlEvent::Note {
+            note: 65.0,
+            duration: 0.5,
+        },
+        MmlEvent::Note {
+            note: 72.0,
+            duration: 0.5,
+        },
+        MmlEvent::Note {
+            note: 58.0,
+            duration: 0.5,
+        },
+        MmlEvent::Note {
+            note: 60.0,
+            duration: 0.5,
+        },
+        MmlEvent::Volume(1.0),
+        MmlEvent::Envelope {
+            shape: 8,
+            period: 255,
+        },
+        MmlEvent::Envelope {
+            shape: 8,
+            period: 100,
+        },
+    ];
+    assert_eq!(events.as_slice(), expected.as_slice());
+
+    assert_eq!(parse("o9c"), Err(ChipError::InvalidMml { position: 0 }));
+    assert_eq!(parse("cde!"), Err(ChipError::InvalidMml { position: 3 }));
+    let dots = alloc::format!("c4{}", ".".repeat(40));
+    assert_eq!(parse(&dots), Err(ChipError::InvalidMml { position: 0 }));
+}
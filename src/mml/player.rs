@@ -0,0 +1,171 @@
+use super::{envelope_from_shape, parse, MmlEvent};
+use crate::{prelude::*, Vec};
+
+/// Owns a [SoundChip] and plays MML strings on it, one string per channel.
+/// Events are applied with sample accuracy while rendering.
+pub struct MmlPlayer {
+    /// The SoundChip driven by this player.
+    pub chip: SoundChip,
+    tracks: Vec<MmlTrack>,
+}
+
+// The playback state of a single MML string.
+struct MmlTrack {
+    events: Vec<MmlEvent>,
+    head: usize,
+    wait: f64,
+    sound: Sound,
+    envelope: Option<(u8, u16)>,
+    finished: bool,
+}
+
+impl MmlPlayer {
+    /// Creates an idle player.
+    pub fn new(chip: SoundChip) -> Self {
+        Self {
+            chip,
+            tracks: Vec::new(),
+        }
+    }
+
+    /// Parses and starts playing MML strings, where the first string plays on channel 0,
+    /// the second string on channel 1, etc. Nothing changes if any of the strings is invalid.
+    pub fn play(&mut self, channels: &[&str]) -> Result<(), ChipError> {
+        if channels.len() > self.chip.channels.len() {
+            return Err(ChipError::InvalidChannel);
+        }
+        let mut tracks = Vec::new();
+        for source in channels {
+            tracks.push(MmlTrack {
+                events: parse(source)?,
+                head: 0,
+                wait: 0.0,
+                sound: Sound {
+                    volume: 8.0 / 15.0,
+                    volume_env: None,
+                    ..Default::default()
+                },
+                envelope: None,
+                finished: false,
+            });
+        }
+        self.stop();
+        self.tracks = tracks;
+        Ok(())
+    }
+
+    /// Stops playback and every channel used by it.
+    pub fn stop(&mut self) {
+        for (index, _) in self.tracks.iter().enumerate() {
+            if let Some(channel) = self.chip.channels.get_mut(index) {
+                channel.stop();
+            }
+        }
+        self.tracks.clear();
+    }
+
+    /// True while any of the MML strings hasn't finished playing.
+    pub fn is_playing(&self) -> bool {
+        self.tracks.iter().any(|track| !track.finished)
+    }
+
+    /// Renders a given number of samples on demand, advancing playback.
+    pub fn iter(&mut self, sample_count: usize) -> MmlPlayerIter<'_> {
+        MmlPlayerIter {
+            player: self,
+            head: 0,
+            sample_count,
+        }
+    }
+
+    /// Process a single sample, advancing playback and the chip's internal timer.
+    pub fn process_sample(&mut self) -> Sample<i16> {
+        let delta_time = 1.0 / self.chip.sample_rate as f64;
+        for (index, track) in self.tracks.iter_mut().enumerate() {
+            if index >= self.chip.channels.len() {
+                break;
+            }
+            track.process(&mut self.chip, index, delta_time);
+        }
+        self.chip.process_sample()
+    }
+}
+
+impl MmlTrack {
+    fn process(&mut self, chip: &mut SoundChip, index: usize, delta_time: f64) {
+        if self.finished {
+            return;
+        }
+        while self.wait <= 0.0 {
+            let Some(event) = self.events.get(self.head) else {
+                chip.channels[index].stop();
+                self.finished = true;
+                return;
+            };
+            self.head += 1;
+            match *event {
+                MmlEvent::Note { note, duration } => {
+                    self.play_note(chip, index, note);
+                    self.wait += duration as f64;
+                }
+                MmlEvent::Rest { duration } => {
+                    chip.channels[index].stop();
+                    self.wait += duration as f64;
+                }
+                MmlEvent::Volume(volume) => {
+                    self.sound.volume = volume;
+                    self.sound.volume_env = None;
+                    self.envelope = None;
+                }
+                MmlEvent::Envelope { shape, period } => {
+                    self.sound.volume = 1.0;
+                    self.sound.volume_env = Some(envelope_from_shape(shape, period));
+                    self.envelope = Some((shape, period));
+                }
+            }
+        }
+        self.wait -= delta_time;
+    }
+
+    // Uses the group's hardware envelope if the channel supports it, like the MSX does.
+    // Otherwise the sound's volume envelope mimics the envelope shape.
+    fn play_note(&self, chip: &mut SoundChip, index: usize, note: f32) {
+        let channel = &mut chip.channels[index];
+        channel.set_sound(&self.sound);
+        let group = channel.group();
+        let hardware = self.envelope.is_some()
+            && group.is_some()
+            && channel.specs().volume.hardware_envelope.is_some();
+        channel.set_hardware_envelope(hardware);
+        channel.set_midi_note(note);
+        channel.play();
+        if !hardware {
+            return;
+        }
+        let group = group.and_then(|group| chip.groups.get_mut(group));
+        if let (Some(group), Some((shape, period))) = (group, self.envelope) {
+            // Writing the shape register restarts the envelope on every note
+            group.envelope.set_period(period as u32);
+            group.envelope.set_shape(shape);
+        }
+    }
+}
+
+/// Iterates a specified number of samples. Use [MmlPlayer::iter()] to obtain this.
+pub struct MmlPlayerIter<'a> {
+    player: &'a mut MmlPlayer,
+    head: usize,
+    sample_count: usize,
+}
+
+impl Iterator for MmlPlayerIter<'_> {
+    type Item = Sample<i16>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.head < self.sample_count {
+            self.head += 1;
+            return Some(self.player.process_sample());
+        }
+        None
+    }
+}
@@ -10,6 +10,12 @@ pub use values::*;
 mod channel;
 pub use channel::*;
 
+mod chip_group;
+pub use chip_group::*;
+
+mod commands;
+pub use commands::*;
+
 mod envelope;
 pub use envelope::*;
 
@@ -19,12 +25,27 @@ pub use envelope_preset::*;
 mod error;
 pub use error::*;
 
+mod filter;
+pub(crate) use filter::*;
+
+mod hardware_envelope;
+pub use hardware_envelope::*;
+
 mod loop_kind;
 pub use loop_kind::*;
 
+mod mixer;
+pub(crate) use mixer::*;
+
 mod note;
 pub use note::*;
 
+mod output;
+pub use output::*;
+
+mod pcm;
+pub use pcm::*;
+
 mod sample;
 pub use sample::*;
 
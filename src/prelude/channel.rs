@@ -1,4 +1,4 @@
-use crate::{math::*, prelude::*, presets::*, rng::*, Vec};
+use crate::{dpcm::*, math::*, prelude::*, presets::*, rng::*, Vec};
 use core::f32::consts::TAU;
 use libm::powf;
 
@@ -7,13 +7,20 @@ const FREQ_C4: f32 = 261.63;
 /// A single sound channel with configurable properties. The easiest way to create a Channel
 /// is using Channel::from(spec), and provide one of the Specs from the "presets" module,
 /// or create your own spec from scratch.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Channel {
     /// All public sound properties
     sound: Sound,
     // Wavetable
     wavetable: Vec<f32>,
     wave_out: f32,
+    // Sampled sound
+    pcm: Option<PcmSample>,
+    pcm_position: f32,
+    pcm_released: bool,
+    dpcm: Option<DpcmSample>,
+    dpcm_decoder: DpcmDecoder,
+    dpcm_time: f32,
     // Timing
     phase: f32,
     time: f32,
@@ -29,8 +36,21 @@ pub struct Channel {
     // Noise
     rng: Rng,
     noise_on: bool,
+    tone_on: bool,
     noise_period: f32,
     noise_output: f32,
+    noise_dirty: bool,
+    noise_written: f32,
+    // Chip group
+    group: Option<usize>,
+    hardware_envelope: bool,
+    hardware_level: f32,
+    hardware_volume: f32,
+    // Output stage, only used while not in a group
+    output_filter: OutputFilter,
+    // Anti-aliasing: the last uncorrected value, and the corrected value waiting to be output
+    blep_last: f32,
+    blep_delayed: f32,
     // State
     specs: SpecsChip,
     pan: NormalSigned,
@@ -48,12 +68,12 @@ pub struct Channel {
 
 impl From<SpecsChip> for Channel {
     fn from(specs: SpecsChip) -> Self {
-        let wave_env: Envelope<NormalSigned> = if let Some(knots) = specs.wavetable.default_waveform
-        {
-            Envelope::from(knots)
-        } else {
-            Envelope::from(KNOTS_WAVE_TRIANGLE)
-        };
+        let wave_env: Envelope<NormalSigned> =
+            if let Some(knots) = &specs.wavetable.default_waveform {
+                Envelope::from(knots.as_ref())
+            } else {
+                Envelope::from(KNOTS_WAVE_TRIANGLE)
+            };
         let mut result = Self {
             // Timing
             phase: 0.0,
@@ -64,6 +84,13 @@ impl From<SpecsChip> for Channel {
             // Wavetable
             wavetable: Self::get_wavetable_from_specs(&specs),
             wave_out: 0.0,
+            // Sampled sound
+            pcm: None,
+            pcm_position: 0.0,
+            pcm_released: false,
+            dpcm: None,
+            dpcm_decoder: DpcmDecoder::default(),
+            dpcm_time: 0.0,
             // Volume
             volume_attn: 0.0,
             // Pitch
@@ -72,8 +99,20 @@ impl From<SpecsChip> for Channel {
             // Noise
             rng: Self::get_rng(&specs),
             noise_on: false,
+            tone_on: true,
             noise_period: 0.0,
             noise_output: 0.0,
+            noise_dirty: false,
+            noise_written: 0.0,
+            // Chip group
+            group: None,
+            hardware_envelope: false,
+            hardware_level: -1.0,
+            hardware_volume: 0.0,
+            // Output stage
+            output_filter: OutputFilter::from(&specs.output),
+            blep_last: 0.0,
+            blep_delayed: 0.0,
             // State
             sound: Sound {
                 waveform: Some(wave_env),
@@ -108,6 +147,9 @@ impl Default for Channel {
 impl Channel {
     /// Allows sound generation on this channel.
     pub fn play(&mut self) {
+        if !self.playing {
+            self.restart_samples();
+        }
         self.playing = true;
         self.set_pitch(self.sound.pitch);
         self.calculate_multipliers();
@@ -139,6 +181,7 @@ impl Channel {
 
     /// "Releases" all envelopes, allowing them to exit their looping state and reach their end.
     pub fn release(&mut self) {
+        self.pcm_released = true;
         if let Some(env) = &mut self.sound.volume_env {
             env.release();
         }
@@ -167,11 +210,26 @@ impl Channel {
         &self.sound
     }
 
+    /// The index of the [ChipGroup] this channel belongs to, if any.
+    pub fn group(&self) -> Option<usize> {
+        self.group
+    }
+
+    /// True if the volume is controlled by the group's hardware envelope.
+    pub fn is_hardware_envelope(&self) -> bool {
+        self.hardware_envelope
+    }
+
     /// True if channel is set to noise, false if set to tone.
     pub fn is_noise(&self) -> bool {
         self.noise_on
     }
 
+    /// False if the tone is disabled with [Channel::set_tone()].
+    pub fn is_tone(&self) -> bool {
+        self.tone_on
+    }
+
     /// Current octave.
     pub fn octave(&self) -> i32 {
         libm::floorf(self.midi_note / 12.0) as i32 - 1
@@ -202,8 +260,19 @@ impl Channel {
         &mut self.wavetable
     }
 
+    /// The sampled sound used instead of the wavetable, if any.
+    pub fn pcm(&self) -> Option<&PcmSample> {
+        self.pcm.as_ref()
+    }
+
+    /// The delta stream used instead of the wavetable, if any.
+    pub fn dpcm(&self) -> Option<&DpcmSample> {
+        self.dpcm.as_ref()
+    }
+
     /// Resets al internal timers (tone, noise, envelopes)
     pub fn reset(&mut self) {
+        self.restart_samples();
         self.time = 0.0;
         self.time_tone = 0.0;
         self.time_noise = 0.0;
@@ -230,8 +299,14 @@ impl Channel {
 
     /// Reconfigures all internals according to new specs
     pub fn set_specs(&mut self, specs: SpecsChip) {
+        if specs.volume.hardware_envelope.is_none() {
+            self.hardware_envelope = false;
+        }
         self.rng = Self::get_rng(&specs);
         self.wavetable = Self::get_wavetable_from_specs(&specs);
+        if specs.output != self.specs.output {
+            self.output_filter = OutputFilter::from(&specs.output);
+        }
         self.specs = specs;
     }
 
@@ -270,6 +345,29 @@ impl Channel {
         Ok(())
     }
 
+    /// Switches the channel to sampled sound playback, or back to the wavetable if None.
+    /// The sample plays from the start on the next [Channel::play()] (or immediately if
+    /// already playing), at a speed relative to its root note.
+    pub fn set_pcm(&mut self, pcm: Option<PcmSample>) {
+        if pcm.is_some() {
+            self.dpcm = None;
+        }
+        self.pcm = pcm;
+        self.restart_samples();
+        self.last_sample_index = usize::MAX;
+    }
+
+    /// Switches the channel to NES DMC playback of a delta stream, or back to the wavetable
+    /// if None. Unlike [Channel::set_pcm()] the playback rate is fixed by the sample,
+    /// and the channel's pitch is ignored.
+    pub fn set_dpcm(&mut self, dpcm: Option<DpcmSample>) {
+        if dpcm.is_some() {
+            self.pcm = None;
+        }
+        self.dpcm = dpcm;
+        self.restart_samples();
+    }
+
     /// A value between 0.0 and 1.0. It will be quantized, receive a fixed gain and
     /// mapped to an exponential curve, according to the SpecsChip.
     pub fn set_volume(&mut self, volume: f32) {
@@ -283,6 +381,32 @@ impl Channel {
         self.calculate_multipliers();
     }
 
+    /// Replaces the current sound's vibratto, without resetting the channel.
+    pub fn set_vibratto(&mut self, vibratto: Option<Vibratto>) {
+        self.sound.vibratto = vibratto;
+    }
+
+    /// Switches the volume control to the group's hardware envelope, if specs allow it.
+    /// While enabled, the channel's volume, volume envelope and tremolo are ignored, like
+    /// the AY-3-8910's envelope mode. Has no effect if the channel doesn't belong to a group.
+    pub fn set_hardware_envelope(&mut self, state: bool) {
+        if self.specs.volume.hardware_envelope.is_some() {
+            self.hardware_envelope = state;
+        }
+    }
+
+    /// Enables or disables the tone, like the AY-3-8910's mixer. Without tone the wavetable is
+    /// replaced by a constant high level, so the volume alone shapes the output. Combined with
+    /// the hardware envelope this produces the "buzzer" sawtooth and triangle waves.
+    /// Enabled by default.
+    pub fn set_tone(&mut self, state: bool) {
+        if state && !self.tone_on {
+            // Back to the last wavetable value, which is still tracked without tone.
+            self.wave_out = self.last_sample_value;
+        }
+        self.tone_on = state;
+    }
+
     /// Switches channel between tone and noise generation, if specs allow noise.
     /// Will be overriden if a noise envelope is used.
     pub fn set_noise(&mut self, state: bool) {
@@ -291,6 +415,12 @@ impl Channel {
         }
     }
 
+    /// Restarts the channel's noise generator from a known state, so that renders
+    /// using noise (including wavetable noise) are reproducible.
+    pub fn set_noise_seed(&mut self, seed: u32) {
+        self.rng.set_state(seed);
+    }
+
     /// Adjusts internal pitch values to correspond to octave and note ( where C = 0, C# = 1, etc.).
     /// "reset_time" forces the waveform to start from position 0, ignoring previous phase.
     pub fn set_note(&mut self, octave: impl Into<i32>, note: impl Into<i32>) {
@@ -326,7 +456,12 @@ impl Channel {
         // SpecsNoise
         match &self.specs.noise {
             SpecsNoise::Random { pitch, .. } | SpecsNoise::Melodic { pitch, .. } => {
-                if let Some(steps) = &pitch.steps {
+                if let Some(divider) = &pitch.divider {
+                    // The divider describes the actual noise hardware, so it's applied
+                    // after the multiplier.
+                    let tone_freq = 1.0 / self.period;
+                    self.noise_period = 1.0 / divider.quantize(tone_freq * pitch.multiplier);
+                } else if let Some(steps) = &pitch.steps {
                     let freq_range = if let Some(range) = &pitch.range {
                         *range.start()..=*range.end()
                     } else {
@@ -343,6 +478,8 @@ impl Channel {
             }
             _ => {}
         }
+        // Forces a write to the group's shared noise period, even if it didn't change.
+        self.noise_dirty = true;
         self.last_env = self.process_envelopes();
     }
 
@@ -367,14 +504,8 @@ impl Channel {
         };
 
         // Quantize volume (if needed) and apply log curve.
-        let volume = libm::powf(
-            if let Some(steps) = self.specs.volume.steps {
-                quantize_range(self.sound.volume * volume_env, steps, 0.0..=1.0)
-            } else {
-                self.sound.volume * volume_env
-            },
-            self.specs.volume.exponent,
-        );
+        let level = self.volume_level(self.sound.volume * volume_env);
+        let volume = self.volume_curve(level);
 
         // Pitch envelope
         let mut pitch_change = if let Some(env) = &mut self.sound.pitch_env {
@@ -396,7 +527,9 @@ impl Channel {
 
         // Acquire optionally quantized tone period and noise period with pitch change
         let base_period = (self.period / self.specs.pitch.multiplier) * powf(2.0, -pitch_change);
-        let tone_period = if let Some(steps) = self.specs.pitch.steps {
+        let tone_period = if let Some(divider) = &self.specs.pitch.divider {
+            1.0 / divider.quantize(1.0 / base_period)
+        } else if let Some(steps) = self.specs.pitch.steps {
             if let Some(range) = &self.specs.pitch.range {
                 // TODO: This needs optimization...
                 let freq = 1.0 / base_period;
@@ -408,7 +541,17 @@ impl Channel {
             base_period
         };
 
-        let noise_period = self.noise_period * powf(2.0, -pitch_change);
+        let noise_divider = match &self.specs.noise {
+            SpecsNoise::Random { pitch, .. } | SpecsNoise::Melodic { pitch, .. } => pitch.divider,
+            _ => None,
+        };
+        let noise_period = match noise_divider {
+            // Pitch changes must snap to the noise divider as well
+            Some(divider) if pitch_change != 0.0 => {
+                1.0 / divider.quantize(powf(2.0, pitch_change) / self.noise_period)
+            }
+            _ => self.noise_period * powf(2.0, -pitch_change),
+        };
         let noise = if let Some(env) = &mut self.sound.noise_env {
             env.peek(self.time_env)
         } else {
@@ -426,38 +569,144 @@ impl Channel {
         // Return
         EnvelopeValues {
             volume,
+            level,
             noise,
             tone_period,
             noise_period,
         }
     }
 
+    // Quantizes the volume to the number of steps, or to the length of the DAC table.
+    fn volume_level(&self, volume: f32) -> f32 {
+        let steps = match &self.specs.volume.table {
+            Some(table) => Some(table.len() as u16),
+            None => self.specs.volume.steps,
+        };
+        if let Some(steps) = steps {
+            quantize_range(volume, steps, 0.0..=1.0)
+        } else {
+            volume
+        }
+    }
+
+    // Maps a quantized volume level through the DAC table, or applies the non-linear curve.
+    fn volume_curve(&self, level: f32) -> f32 {
+        match &self.specs.volume.table {
+            Some(table) if !table.is_empty() => {
+                let last = table.len() - 1;
+                let index = libm::roundf(level * last as f32) as usize;
+                table[index.min(last)]
+            }
+            _ => libm::powf(level, self.specs.volume.exponent),
+        }
+    }
+
+    // The current volume, from the envelopes or from the group's hardware envelope.
+    #[inline(always)]
+    fn current_volume(&mut self, group: Option<&ChipGroup>) -> f32 {
+        let Some(group) = group else {
+            return self.last_env.volume;
+        };
+        // The group's mix table replaces the volume curve, so the level stays linear.
+        if let (Some(_), Some(mix)) = (&group.mix_table, &self.specs.volume.mix_table) {
+            let level = if self.hardware_envelope {
+                group.envelope.level()
+            } else {
+                self.last_env.level
+            };
+            return level * mix.weight;
+        }
+        if !self.hardware_envelope {
+            return self.last_env.volume;
+        }
+        let level = group.envelope.level();
+        if level != self.hardware_level {
+            self.hardware_level = level;
+            self.hardware_volume = self.volume_curve(self.volume_level(level));
+        }
+        self.hardware_volume
+    }
+
     #[inline(always)]
     /// Returns the current sample and peeks the internal timer.
-    pub(crate) fn sample(&mut self, delta_time: f32) -> Sample<f32> {
+    pub(crate) fn sample(&mut self, delta_time: f32, group: Option<&mut ChipGroup>) -> Sample<f32> {
+        if self.playing && self.envelopes_due() {
+            self.last_env = self.process_envelopes();
+        }
+        self.sample_wave(delta_time, group)
+    }
+
+    // Renders a span for a channel outside of any group, adding it to the output buffers.
+    // Envelopes with a fixed rate are processed once per period, so the span is rendered in
+    // segments between them and the samples in each segment skip the envelope checks.
+    pub(crate) fn render_span(
+        &mut self,
+        deltas: &[f32],
+        left: &mut [f32],
+        right: &mut [f32],
+        sample_rate: u32,
+    ) {
+        let mut start = 0;
+        while start < deltas.len() {
+            if self.playing && self.envelopes_due() {
+                self.last_env = self.process_envelopes();
+            }
+            let end = start + self.samples_until_envelopes(&deltas[start..]);
+            for i in start..end {
+                if self.is_silent() {
+                    return;
+                }
+                let sample = self.sample_wave(deltas[i], None);
+                let sample = self.filter_output(sample, sample_rate);
+                left[i] += sample.left;
+                right[i] += sample.right;
+            }
+            start = end;
+        }
+    }
+
+    // True once the envelope period has elapsed, for specs with an envelope rate.
+    #[inline(always)]
+    fn envelopes_due(&self) -> bool {
+        self.specs.envelope_rate.is_some()
+            && (self.time - self.last_env_time >= self.env_period || self.time == 0.0)
+    }
+
+    // How many samples can be rendered before envelopes are due again, at least one.
+    // Advances a copy of the channel time exactly like rendering the samples does.
+    fn samples_until_envelopes(&self, deltas: &[f32]) -> usize {
+        if self.specs.envelope_rate.is_none() || !self.playing {
+            return deltas.len();
+        }
+        let mut time = self.time;
+        for (count, delta_time) in deltas.iter().enumerate() {
+            if count > 0 && (time - self.last_env_time >= self.env_period || time == 0.0) {
+                return count;
+            }
+            time += delta_time;
+        }
+        deltas.len()
+    }
+
+    // A single sample with the envelopes already processed, unless the specs have no
+    // envelope rate. Those process envelopes at the start of each wave cycle instead.
+    #[inline(always)]
+    fn sample_wave(&mut self, delta_time: f32, mut group: Option<&mut ChipGroup>) -> Sample<f32> {
         // Always apply attenuation, so that values always drift to zero
         self.wave_out *= self.volume_attn;
 
         // Early return if not playing
         if !self.playing {
+            self.blep_last = 0.0;
+            self.blep_delayed = 0.0;
             return Sample {
                 left: self.wave_out * self.left_mult,
                 right: self.wave_out * self.right_mult,
             };
         }
 
-        // Envelope processing
-        let process_envelopes_now = if self.specs.envelope_rate.is_some() {
-            // If there's an envelope rate, calculate envelopes when needed.
-            if (self.time - self.last_env_time >= self.env_period) || self.time == 0.0 {
-                true
-            } else {
-                false
-            }
-        } else {
-            // If no envelope rate, always calculate envelopes
-            true
-        };
+        // Without an envelope rate, envelopes are calculated here instead
+        let process_envelopes_now = self.specs.envelope_rate.is_none();
 
         // Generate noise level, will be mixed later
         if self.last_env.noise > 0.0 {
@@ -468,7 +717,15 @@ impl Channel {
                     if process_envelopes_now {
                         self.last_env = self.process_envelopes();
                     }
-                    if self.time_noise >= self.last_env.noise_period {
+                    if let Some(noise) = group.as_mut().and_then(|g| g.noise.as_mut()) {
+                        // Shared noise: the last channel to write the period wins.
+                        if self.noise_dirty || self.noise_written != self.last_env.noise_period {
+                            self.noise_dirty = false;
+                            self.noise_written = self.last_env.noise_period;
+                            noise.set_period(self.noise_written);
+                        }
+                        noise.output()
+                    } else if self.time_noise >= self.last_env.noise_period {
                         self.time_noise = 0.0;
                         (quantize_range(self.rng.next_f32(), volume_steps as u16, 0.0..=1.0) * 2.0)
                             - 1.0
@@ -476,10 +733,19 @@ impl Channel {
                         self.noise_output
                     }
                 }
+                // Applied directly to the wavetable samples instead
                 SpecsNoise::WaveTable { .. } => 0.0,
             };
         }
 
+        // Sampled sound replaces the wavetable entirely
+        if self.pcm.is_some() {
+            return self.sample_pcm(delta_time, process_envelopes_now, group);
+        }
+        if self.dpcm.is_some() {
+            return self.sample_dpcm(delta_time, process_envelopes_now, group);
+        }
+
         // Determine wavetable index
         let len = self.wavetable.len();
         let index = if self.specs.wavetable.use_loop {
@@ -489,9 +755,24 @@ impl Channel {
         };
 
         // Obtain wavetable sample and set it to output
+        let mut edge = None;
         if index != self.last_sample_index {
             self.last_sample_index = index;
-            let wave = self.wavetable[index];
+            // How far past the wavetable sample boundary we are, in output samples.
+            let increment = delta_time / self.last_env.tone_period * len as f32;
+            if self.specs.wavetable.anti_aliasing == AntiAliasing::PolyBlep && increment > 0.0 {
+                let position = self.phase * len as f32;
+                edge = Some(((position - index as f32) / increment).clamp(0.0, 1.0));
+            }
+            let mut wave = self.wavetable[index];
+            // Wavetable noise randomizes each sample, so every cycle is slightly different.
+            if let SpecsNoise::WaveTable { mix } = self.specs.noise {
+                let amount = (mix * self.last_env.noise).clamp(0.0, 1.0);
+                if amount > 0.0 {
+                    let noise = (self.rng.next_f32() * 2.0) - 1.0;
+                    wave += (noise - wave) * amount;
+                }
+            }
             let value = if let Some(steps) = self.specs.wavetable.steps {
                 quantize_range(wave, steps, -1.0..=1.0)
             } else {
@@ -500,11 +781,11 @@ impl Channel {
             // Avoids resetting attenuation if value hasn't changed
             if value != self.last_sample_value {
                 // Prevents sampling envelope in the middle of a wave cycle
-                let cycle_index =
-                    (self.time_tone as f64 / self.last_env.tone_period as f64) as usize;
-                if cycle_index != self.last_cycle_index {
-                    self.last_cycle_index = cycle_index;
-                    if process_envelopes_now {
+                if process_envelopes_now {
+                    let cycle_index =
+                        (self.time_tone as f64 / self.last_env.tone_period as f64) as usize;
+                    if cycle_index != self.last_cycle_index {
+                        self.last_cycle_index = cycle_index;
                         self.last_env = self.process_envelopes();
                     }
                 }
@@ -513,36 +794,212 @@ impl Channel {
             }
         }
 
+        // Held high without tone, also preventing the attenuation from fading it.
+        if !self.tone_on {
+            self.wave_out = 1.0;
+        }
+
         // adjust timers
         self.time += delta_time;
         self.time_noise += delta_time;
         self.time_env += delta_time;
         self.time_tone += delta_time;
         self.phase = if self.specs.wavetable.use_loop {
-            (self.time_tone % self.last_env.tone_period) / self.last_env.tone_period
+            // Advanced by the same step as "time_tone" instead of taking its remainder, which
+            // is much slower. Both are synced again whenever the envelopes are processed.
+            let phase = self.phase + delta_time / self.last_env.tone_period;
+            if phase >= 1.0 {
+                phase - libm::floorf(phase)
+            } else {
+                phase
+            }
         } else {
             self.time_tone / self.last_env.tone_period // TODO: Needs testing
         };
 
-        // Mix with noise (currently just overwrites). TODO: optional mix
-        if self.last_env.noise > 0.0 {
-            self.wave_out = self.noise_output;
+        // Mix with noise. Overwrite replaces the wave itself, other modes only affect this sample.
+        let mut mixed = self.wave_out;
+        let wavetable_noise = matches!(self.specs.noise, SpecsNoise::WaveTable { .. });
+        if self.last_env.noise > 0.0 && !wavetable_noise {
+            match self.specs.noise_mix {
+                NoiseMix::Overwrite => {
+                    self.wave_out = self.noise_output;
+                    mixed = self.wave_out;
+                }
+                mix => mixed = mix.mix(self.wave_out, self.noise_output, self.last_env.noise),
+            }
         }
 
-        // Apply volume and optionally clamp to positive values
-        let output = if self.specs.volume.clip_negative_values {
-            self.wave_out.clamp(0.0, 1.0) * self.last_env.volume
-        } else {
-            self.wave_out * self.last_env.volume
-        };
+        if self.specs.wavetable.anti_aliasing == AntiAliasing::PolyBlep {
+            // Clipped first, so the smoothed edges aren't clipped afterwards.
+            if self.specs.volume.clip_negative_values {
+                mixed = mixed.clamp(0.0, 1.0);
+            }
+            mixed = self.poly_blep(mixed, edge);
+        }
+        self.output(mixed, group.as_deref())
+    }
 
-        // Return sample with volume and pan applied
+    // Corrects a step from the last value with a two sample polynomial BLEP, "edge" being how
+    // far the current sample is past the step. Returns the previous, fully corrected value.
+    #[inline(always)]
+    fn poly_blep(&mut self, value: f32, edge: Option<f32>) -> f32 {
+        let mut previous = self.blep_delayed;
+        let mut current = value;
+        if let Some(edge) = edge {
+            let half_step = (value - self.blep_last) * 0.5;
+            previous += half_step * edge * edge;
+            current -= half_step * (1.0 - edge) * (1.0 - edge);
+        }
+        self.blep_last = value;
+        self.blep_delayed = current;
+        previous
+    }
+
+    // A stopped channel only decays towards zero, nothing to render once it gets there.
+    #[inline(always)]
+    pub(crate) fn is_silent(&self) -> bool {
+        !self.playing && self.wave_out == 0.0 && self.output_filter.is_idle()
+    }
+
+    // Applies the channel's own output stage, for channels that aren't in a group.
+    #[inline(always)]
+    pub(crate) fn filter_output(&mut self, sample: Sample<f32>, sample_rate: u32) -> Sample<f32> {
+        let (left, right) = self
+            .output_filter
+            .process(sample.left, sample.right, sample_rate);
+        Sample { left, right }
+    }
+
+    // Applies volume and pan to a wave value, optionally clamping it to positive values.
+    fn output(&mut self, value: f32, group: Option<&ChipGroup>) -> Sample<f32> {
+        let output = self.output_value(value, group);
         Sample {
             left: output * self.left_mult,
             right: output * self.right_mult,
         }
     }
 
+    // Applies volume to a wave value, before panning.
+    #[inline(always)]
+    fn output_value(&mut self, value: f32, group: Option<&ChipGroup>) -> f32 {
+        let volume = self.current_volume(group);
+        if self.specs.volume.clip_negative_values {
+            value.clamp(0.0, 1.0) * volume
+        } else {
+            value * volume
+        }
+    }
+
+    // Sample playback, used instead of the wavetable when a PcmSample is set.
+    fn sample_pcm(
+        &mut self,
+        delta_time: f32,
+        process_envelopes_now: bool,
+        group: Option<&mut ChipGroup>,
+    ) -> Sample<f32> {
+        if process_envelopes_now {
+            self.last_env = self.process_envelopes();
+        }
+        let Some(pcm) = &self.pcm else {
+            return Sample {
+                left: 0.0,
+                right: 0.0,
+            };
+        };
+        let Some(position) = pcm.wrap(self.pcm_position, self.pcm_released) else {
+            // Reached the end of a one-shot sample or release tail
+            self.stop();
+            return Sample {
+                left: 0.0,
+                right: 0.0,
+            };
+        };
+
+        // Obtain sample and quantize it to the wavetable steps
+        let index = position as usize;
+        if index != self.last_sample_index {
+            self.last_sample_index = index;
+            let value = if let Some(steps) = self.specs.wavetable.steps {
+                quantize_range(pcm.data[index], steps, -1.0..=1.0)
+            } else {
+                pcm.data[index]
+            };
+            if value != self.last_sample_value {
+                self.wave_out = value;
+                self.last_sample_value = value;
+            }
+        }
+
+        // Advance, following the pitch including envelopes
+        let frequency = 1.0 / self.last_env.tone_period;
+        self.pcm_position = position + (pcm.rate_at(frequency) * delta_time);
+        self.time += delta_time;
+        self.time_noise += delta_time;
+        self.time_env += delta_time;
+
+        self.output(self.wave_out, group.as_deref())
+    }
+
+    // DMC playback, used instead of the wavetable when a DpcmSample is set.
+    // Runs at the sample's fixed rate, ignoring the channel's pitch.
+    fn sample_dpcm(
+        &mut self,
+        delta_time: f32,
+        process_envelopes_now: bool,
+        group: Option<&mut ChipGroup>,
+    ) -> Sample<f32> {
+        if process_envelopes_now {
+            self.last_env = self.process_envelopes();
+        }
+        let Some(dpcm) = &self.dpcm else {
+            return Sample {
+                left: 0.0,
+                right: 0.0,
+            };
+        };
+        let period = 1.0 / dpcm.frequency();
+        self.dpcm_time += delta_time;
+        while self.dpcm_time >= period {
+            self.dpcm_time -= period;
+            if !self.dpcm_decoder.step(dpcm) {
+                // The DMC holds its last level after the sample ends. Stopped channels output
+                // "wave_out" as is, so it keeps the volume to continue from the same value.
+                let held = self.output_value(self.wave_out, group.as_deref());
+                self.stop();
+                self.wave_out = held;
+                return Sample {
+                    left: held * self.left_mult,
+                    right: held * self.right_mult,
+                };
+            }
+        }
+        let level = self.dpcm_decoder.output();
+        self.wave_out = if let Some(steps) = self.specs.wavetable.steps {
+            quantize_range(level, steps, -1.0..=1.0)
+        } else {
+            level
+        };
+        self.time += delta_time;
+        self.time_env += delta_time;
+        self.output(self.wave_out, group.as_deref())
+    }
+
+    // Sampled sounds start from the beginning
+    fn restart_samples(&mut self) {
+        self.pcm_position = 0.0;
+        self.pcm_released = false;
+        self.dpcm_time = 0.0;
+        if let Some(dpcm) = &self.dpcm {
+            self.dpcm_decoder = DpcmDecoder::new(dpcm);
+        }
+    }
+
+    // Assigned by the SoundChip, which owns the groups.
+    pub(crate) fn set_group(&mut self, group: Option<usize>) {
+        self.group = group;
+    }
+
     // Must be called after setting volume or pan.
     // Used to pre-calculate as many values as possible instead of doing it per sample, since
     // this function is called much less frequently (by orders of magnitude)
@@ -577,8 +1034,8 @@ impl Channel {
     // New Wavetable Vec from specs
     fn get_wavetable_from_specs(specs: &SpecsChip) -> Vec<f32> {
         let mut envelope: Envelope<NormalSigned> =
-            if let Some(knots) = specs.wavetable.default_waveform {
-                knots.into()
+            if let Some(knots) = &specs.wavetable.default_waveform {
+                knots.as_ref().into()
             } else {
                 KNOTS_WAVE_TRIANGLE.into()
             };
@@ -606,10 +1063,12 @@ impl Channel {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 struct EnvelopeValues {
     volume: f32, // TODO: Normal
-    noise: f32,  // TODO: Normal
+    // Quantized volume before the curve, used by groups with a mix table.
+    level: f32,
+    noise: f32, // TODO: Normal
     tone_period: f32,
     noise_period: f32,
 }
@@ -618,9 +1077,177 @@ impl Default for EnvelopeValues {
     fn default() -> Self {
         Self {
             volume: 1.0,
+            level: 1.0,
             noise: 0.0,
             tone_period: 1.0 / FREQ_C4,
             noise_period: 1.0 / FREQ_C4,
         }
     }
 }
+
+#[test]
+fn wavetable_noise_is_reproducible() {
+    let specs = SpecsChip {
+        noise: SpecsNoise::WaveTable { mix: 0.5 },
+        ..SPEC_CHIP_SCC
+    };
+    let render_with = |specs: &SpecsChip, seed: u32| -> Vec<f32> {
+        let mut channel = Channel::from(specs.clone());
+        channel.set_noise_seed(seed);
+        channel.set_noise(true);
+        channel.set_note(4, Note::A);
+        channel.play();
+        (0..2000)
+            .map(|_| channel.sample(1.0 / 44100.0, None).left)
+            .collect()
+    };
+    let a = render_with(&specs, 7);
+    assert_eq!(a, render_with(&specs, 7));
+    assert_ne!(a, render_with(&specs, 8));
+
+    // No noise when mix is zero.
+    let clean = SpecsChip {
+        noise: SpecsNoise::WaveTable { mix: 0.0 },
+        ..SPEC_CHIP_SCC
+    };
+    assert_eq!(render_with(&clean, 7), render_with(&clean, 8));
+}
+
+#[test]
+fn pcm_playback() {
+    let data: Vec<f32> = (0..100).map(|i| if i < 50 { 1.0 } else { -1.0 }).collect();
+    let mut channel = Channel::from(SPEC_CHIP_CLEAN);
+    channel.set_sound(&Sound {
+        volume_env: None,
+        ..Default::default()
+    });
+    channel.set_pcm(Some(
+        PcmSample::new(data.clone(), 100.0).with_root_note(60.0),
+    ));
+    channel.set_midi_note(72.0);
+    channel.play();
+    // Twice as fast as the original, 100 samples last 0.5 seconds at 100Hz
+    for _ in 0..48 {
+        channel.sample(0.01, None);
+    }
+    assert!(channel.is_playing());
+    for _ in 0..4 {
+        channel.sample(0.01, None);
+    }
+    assert!(!channel.is_playing());
+
+    // Looping sample keeps playing until released, then plays the tail.
+    let pcm = PcmSample::new(data, 100.0).with_loop(PcmLoop::ForwardRelease { start: 0, end: 50 });
+    channel.set_pcm(Some(pcm));
+    channel.set_midi_note(60.0);
+    channel.play();
+    for _ in 0..500 {
+        assert!(channel.sample(0.01, None).left >= 0.0);
+    }
+    assert!(channel.is_playing());
+    channel.release();
+    let tail: Vec<f32> = (0..100).map(|_| channel.sample(0.01, None).left).collect();
+    assert!(tail.iter().any(|value| *value < 0.0));
+    assert!(!channel.is_playing());
+}
+
+#[test]
+fn dpcm_playback() {
+    let mut channel = Channel::from(SPEC_CHIP_NES_DMC);
+    channel.set_sound(&Sound {
+        volume_env: None,
+        ..Default::default()
+    });
+    // 17 bytes of "up" deltas, starting from the bottom.
+    let dpcm = DpcmSample {
+        data: Vec::from([0xFF; 17]),
+        rate: 15,
+        initial_level: 0,
+        ..Default::default()
+    };
+    let duration = (17.0 * 8.0) / dpcm.frequency();
+    channel.set_dpcm(Some(dpcm));
+    channel.play();
+    let delta = 1.0 / 44100.0;
+    let count = (duration / delta) as usize;
+    let mut last = f32::MIN;
+    let mut values = Vec::new();
+    for _ in 0..count - 10 {
+        let value = channel.sample(delta, None).left;
+        assert!(value >= last);
+        last = value;
+        values.push(value);
+    }
+    // Levels below the middle are audible too.
+    assert!(values[40] > values[0]);
+    assert!(channel.is_playing());
+    for _ in 0..20 {
+        let value = channel.sample(delta, None).left;
+        // Holds the last level without jumping when the stream ends.
+        assert!((value - last).abs() < 0.05);
+        last = value;
+    }
+    assert!(!channel.is_playing());
+}
+
+#[test]
+fn poly_blep_reduces_aliasing() {
+    let render_with = |anti_aliasing: AntiAliasing| -> Vec<f32> {
+        let mut channel = Channel::from(SpecsChip {
+            wavetable: SpecsWavetable {
+                anti_aliasing,
+                ..SPEC_WAVE_PSG
+            },
+            pitch: SPEC_PITCH_CLEAN,
+            ..SPEC_CHIP_PSG
+        });
+        channel.set_pitch(3100.0);
+        channel.play();
+        (0..4410)
+            .map(|_| channel.sample(1.0 / 44100.0, None).left)
+            .collect()
+    };
+    // Goertzel magnitude of a single frequency.
+    let magnitude = |samples: &[f32], frequency: f32| -> f32 {
+        let coef = 2.0 * libm::cosf(TAU * frequency / 44100.0);
+        let (mut s1, mut s2) = (0.0, 0.0);
+        for sample in samples {
+            let s0 = sample + coef * s1 - s2;
+            s2 = s1;
+            s1 = s0;
+        }
+        libm::sqrtf(s1 * s1 + s2 * s2 - coef * s1 * s2)
+    };
+    let naive = render_with(AntiAliasing::None);
+    let smooth = render_with(AntiAliasing::PolyBlep);
+    // The 11th harmonic folds back to 10 kHz, the fundamental is kept.
+    assert!(magnitude(&smooth, 10_000.0) < magnitude(&naive, 10_000.0) * 0.25);
+    let fundamental = magnitude(&smooth, 3100.0) / magnitude(&naive, 3100.0);
+    assert!((fundamental - 1.0).abs() < 0.05);
+}
+
+#[test]
+fn tone_off_outputs_hardware_envelope() {
+    // Sample to sample changes, with a 100 Hz sawtooth envelope on a single channel.
+    let render = |tone: bool| {
+        let mut chip = SoundChip::new_msx(44100);
+        chip.groups[0].envelope.set_shape(8);
+        chip.groups[0].envelope.set_frequency(100.0);
+        let channel = &mut chip.channels[1];
+        channel.set_hardware_envelope(true);
+        channel.set_tone(tone);
+        channel.play();
+        let values: Vec<i32> = chip.iter(4410).map(|s| s.left as i32).collect();
+        let changes: Vec<i32> = values.windows(2).map(|pair| pair[1] - pair[0]).collect();
+        changes
+    };
+    // Falls in small steps, and jumps back up at the start of each of the next 9 cycles.
+    let changes = render(false);
+    assert!(changes.iter().all(|change| *change > -3000));
+    let cycles = changes.iter().filter(|change| **change > 3000).count();
+    assert_eq!(cycles, 9);
+
+    // With tone, the square wave gates the sawtooth.
+    let changes = render(true);
+    assert!(changes.iter().filter(|change| **change < -3000).count() > 5);
+}
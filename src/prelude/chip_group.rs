@@ -0,0 +1,194 @@
+use super::{HardwareEnvelope, OutputFilter, SpecsChip, SpecsNoise, SpecsOutput};
+use crate::{math::quantize_range, rng::Rng};
+use alloc::borrow::Cow;
+
+/// Resources shared by a group of channels that belong to the same physical chip,
+/// like the AY-3-8910's single envelope generator and noise generator. Channels are
+/// assigned to a group with [super::SoundChip::set_group()].
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ChipGroup {
+    /// Envelope generator used by channels with the hardware envelope enabled.
+    pub envelope: HardwareEnvelope,
+    /// Optional noise generator. If present, channels in this group producing noise
+    /// will use it instead of their own, and the noise period becomes a group-wide
+    /// register: whichever channel changed it last defines the pitch for all of them.
+    pub noise: Option<SharedNoise>,
+    /// Optional lookup for the combined output of the channels, like the NES "pulse_table".
+    /// Only channels whose specs define a [SpecsMixTable](super::SpecsMixTable) are affected.
+    /// Each side is looked up separately, as if the whole mix were panned to it.
+    pub mix_table: Option<Cow<'static, [f32]>>,
+    // Output stage shared by all channels in the group, and their summed output.
+    output: OutputFilter,
+    mix: (f32, f32),
+}
+
+impl From<&SpecsChip> for ChipGroup {
+    /// Creates a group with resources configured by the specs, if they define them.
+    fn from(specs: &SpecsChip) -> Self {
+        Self {
+            envelope: specs
+                .volume
+                .hardware_envelope
+                .as_ref()
+                .map(HardwareEnvelope::from)
+                .unwrap_or_default(),
+            noise: SharedNoise::from_specs(&specs.noise),
+            mix_table: specs
+                .volume
+                .mix_table
+                .as_ref()
+                .map(|mix| mix.levels.clone()),
+            output: OutputFilter::from(&specs.output),
+            mix: (0.0, 0.0),
+        }
+    }
+}
+
+impl ChipGroup {
+    /// The analog output stage applied to the combined output of the group's channels.
+    pub fn output(&self) -> &SpecsOutput {
+        self.output.specs()
+    }
+
+    /// Replaces the output stage, resetting its filters.
+    pub fn set_output(&mut self, specs: &SpecsOutput) {
+        self.output = OutputFilter::from(specs);
+    }
+
+    // Adds a channel's output to the group's mix for the current sample.
+    #[inline(always)]
+    pub(crate) fn add_output(&mut self, left: f32, right: f32) {
+        self.mix.0 += left;
+        self.mix.1 += right;
+    }
+
+    // Filters and returns the group's mix for the current sample, clearing it for the next.
+    pub(crate) fn take_output(&mut self, sample_rate: u32) -> (f32, f32) {
+        let (left, right) = core::mem::take(&mut self.mix);
+        match &self.mix_table {
+            Some(table) => {
+                let (left, right) = (mix_lookup(table, left), mix_lookup(table, right));
+                self.output.process(left, right, sample_rate)
+            }
+            None => self.output.process(left, right, sample_rate),
+        }
+    }
+
+    /// Advances all shared resources by one sample.
+    pub(crate) fn advance(&mut self, delta_time: f32) {
+        self.envelope.advance(delta_time);
+        if let Some(noise) = &mut self.noise {
+            noise.advance(delta_time);
+        }
+    }
+}
+
+// Interpolated table lookup, mirrored for negative values. Channel outputs are halved by a
+// centered pan, so the position is doubled and the result halved to match.
+fn mix_lookup(table: &[f32], value: f32) -> f32 {
+    let Some(last) = table.len().checked_sub(1) else {
+        return value;
+    };
+    let position = (value.abs() * 2.0).min(last as f32);
+    let index = position as usize;
+    let next = (index + 1).min(last);
+    let level = table[index] + (table[next] - table[index]) * (position - index as f32);
+    libm::copysignf(level, value) * 0.5
+}
+
+/// A single LFSR noise generator with its own period, shared by a [ChipGroup].
+#[derive(Debug, Clone, PartialEq)]
+pub struct SharedNoise {
+    rng: Rng,
+    volume_steps: u8,
+    period: f32,
+    time: f32,
+    output: f32,
+}
+
+impl SharedNoise {
+    /// Creates a noise generator matching the noise specs. Returns None if the specs
+    /// can't produce noise, or if the noise is generated per wavetable cycle.
+    pub fn from_specs(specs: &SpecsNoise) -> Option<Self> {
+        let (rng, volume_steps) = match specs {
+            SpecsNoise::Random { volume_steps, .. } => (Rng::new(15, 1), *volume_steps),
+            SpecsNoise::Melodic {
+                lfsr_length,
+                volume_steps,
+                ..
+            } => (Rng::new(*lfsr_length as u32, 1), *volume_steps),
+            SpecsNoise::None | SpecsNoise::WaveTable { .. } => return None,
+        };
+        Some(Self {
+            rng,
+            volume_steps,
+            period: 1.0,
+            time: 0.0,
+            output: 0.0,
+        })
+    }
+
+    /// The current period in seconds.
+    pub fn period(&self) -> f32 {
+        self.period
+    }
+
+    /// Sets the period in seconds, like writing to the chip's noise period register.
+    pub fn set_period(&mut self, period: f32) {
+        self.period = period;
+    }
+
+    /// The current output, from -1.0 to 1.0.
+    pub fn output(&self) -> f32 {
+        self.output
+    }
+
+    fn advance(&mut self, delta_time: f32) {
+        self.time += delta_time;
+        if self.time >= self.period {
+            self.time = 0.0;
+            let value = quantize_range(self.rng.next_f32(), self.volume_steps as u16, 0.0..=1.0);
+            self.output = (value * 2.0) - 1.0;
+        }
+    }
+}
+
+#[test]
+fn shared_noise_last_writer_wins() {
+    use crate::prelude::*;
+    let mut chip = SoundChip::new_msx(44100);
+    for index in 0..2 {
+        let channel = &mut chip.channels[index];
+        channel.set_specs(crate::presets::SPEC_CHIP_PSG_NOISE);
+        channel.set_noise(true);
+        channel.play();
+    }
+    chip.channels[0].set_note(3, Note::C);
+    chip.channels[1].set_note(5, Note::C);
+    chip.process_sample();
+    let high = chip.groups[0].noise.as_ref().unwrap().period();
+
+    // Channel 0 writes last, so its lower noise pitch is used by both channels.
+    chip.channels[0].set_note(3, Note::C);
+    chip.process_sample();
+    let low = chip.groups[0].noise.as_ref().unwrap().period();
+    assert!(low > high);
+
+    // Nothing changes if nobody writes.
+    for _ in chip.iter(100) {}
+    assert_eq!(chip.groups[0].noise.as_ref().unwrap().period(), low);
+}
+
+#[test]
+fn mix_table_compresses_combined_channels() {
+    use crate::presets::*;
+    // A centered pulse channel at full volume outputs 0.5 per side, like an ungrouped one.
+    let one = mix_lookup(TABLE_MIX_NES_PULSE, 7.5);
+    assert!((one - 0.5).abs() < 0.0001);
+    assert_eq!(mix_lookup(TABLE_MIX_NES_PULSE, -7.5), -one);
+    // Two of them are noticeably less than twice as loud.
+    let two = mix_lookup(TABLE_MIX_NES_PULSE, 15.0);
+    assert!(two > one && two < one * 1.9);
+    // Past the end of the table
+    assert_eq!(mix_lookup(TABLE_MIX_NES_PULSE, 100.0), two);
+}
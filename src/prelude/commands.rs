@@ -0,0 +1,285 @@
+use crate::prelude::{Channel, ChipError, Sound, Vibratto};
+use alloc::{boxed::Box, sync::Arc};
+use core::{
+    cell::UnsafeCell,
+    mem::MaybeUninit,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+/// An operation on the whole [SoundChip](crate::prelude::SoundChip).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ChipOp {
+    PlayAllChannels,
+    StopAllChannels,
+    /// Applies an operation to the channel at this index. Invalid indices are ignored.
+    Channel {
+        index: usize,
+        op: ChannelOp,
+    },
+}
+
+/// An operation on a single [Channel](crate::prelude::Channel). Each variant maps to the
+/// Channel method with the same name.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ChannelOp {
+    Play,
+    PlayAndRelease,
+    Stop,
+    Release,
+    Reset,
+    Noise(bool),
+    Tone(bool),
+    HardwareEnvelope(bool),
+    Volume(f32),
+    Pan(f32),
+    /// MIDI note, C4 is 60.
+    MidiNote(f32),
+    /// Frequency in Hz.
+    Pitch(f32),
+    Vibratto(Option<Vibratto>),
+}
+
+impl ChannelOp {
+    /// Applies the operation to a channel.
+    pub fn apply(self, channel: &mut Channel) {
+        match self {
+            ChannelOp::Play => channel.play(),
+            ChannelOp::PlayAndRelease => channel.play_and_release(),
+            ChannelOp::Stop => channel.stop(),
+            ChannelOp::Release => channel.release(),
+            ChannelOp::Reset => channel.reset(),
+            ChannelOp::Noise(state) => channel.set_noise(state),
+            ChannelOp::Tone(state) => channel.set_tone(state),
+            ChannelOp::HardwareEnvelope(state) => channel.set_hardware_envelope(state),
+            ChannelOp::Volume(volume) => channel.set_volume(volume),
+            ChannelOp::Pan(pan) => channel.set_pan(pan),
+            ChannelOp::MidiNote(note) => channel.set_midi_note(note),
+            ChannelOp::Pitch(frequency) => channel.set_pitch(frequency),
+            ChannelOp::Vibratto(vibratto) => channel.set_vibratto(vibratto),
+        }
+    }
+}
+
+/// An event for [SoundChip::schedule()](crate::prelude::SoundChip::schedule). Unlike a
+/// [ChannelOp] it can own data like a [Sound], so it can't be sent through a command queue.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ChannelEvent {
+    Op(ChannelOp),
+    SetSound(Sound),
+    PlaySound { sound: Sound, release: bool },
+}
+
+impl From<ChannelOp> for ChannelEvent {
+    fn from(op: ChannelOp) -> Self {
+        ChannelEvent::Op(op)
+    }
+}
+
+impl ChannelEvent {
+    /// Applies the event to a channel.
+    pub fn apply(&self, channel: &mut Channel) {
+        match self {
+            ChannelEvent::Op(op) => op.apply(channel),
+            ChannelEvent::SetSound(sound) => channel.set_sound(sound),
+            ChannelEvent::PlaySound { sound, release } => channel.play_sound(sound, *release),
+        }
+    }
+}
+
+/// A [ChipOp] to be applied when the chip reaches a specific sample.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Command {
+    /// The chip sample at which the operation is applied. Commands that arrive late are
+    /// applied on the next processed sample.
+    pub sample: usize,
+    pub op: ChipOp,
+}
+
+/// Creates a single producer, single consumer command queue with room for "capacity"
+/// commands. Only this function allocates: sending and receiving are lock free and
+/// allocation free, so the receiver can be used from an audio callback.
+pub fn command_queue(capacity: usize, sample_rate: u32) -> (CommandSender, CommandReceiver) {
+    let capacity = capacity.max(1);
+    let shared = Arc::new(Shared {
+        slots: (0..capacity)
+            .map(|_| UnsafeCell::new(MaybeUninit::uninit()))
+            .collect(),
+        head: AtomicUsize::new(0),
+        tail: AtomicUsize::new(0),
+        clock: AtomicUsize::new(0),
+        sample_rate,
+    });
+    (
+        CommandSender {
+            shared: shared.clone(),
+        },
+        CommandReceiver { shared },
+    )
+}
+
+// Ring buffer shared by both ends. "head" and "tail" are ever increasing counters: "head"
+// is only written by the receiver, "tail" only by the sender.
+struct Shared {
+    slots: Box<[UnsafeCell<MaybeUninit<Command>>]>,
+    head: AtomicUsize,
+    tail: AtomicUsize,
+    clock: AtomicUsize,
+    sample_rate: u32,
+}
+
+// Safety: a slot is only written by the sender while it's outside the head..tail range,
+// and only read by the receiver while inside it. The release/acquire pairs on head and
+// tail make each write visible before the slot changes hands.
+unsafe impl Sync for Shared {}
+
+/// The producing end of a command queue, usually owned by the game thread.
+pub struct CommandSender {
+    shared: Arc<Shared>,
+}
+
+impl CommandSender {
+    /// Queues an operation to be applied on the next processed sample.
+    pub fn send(&mut self, op: ChipOp) -> Result<(), ChipError> {
+        self.send_at(0, op)
+    }
+
+    /// Queues an operation to be applied at a specific chip sample. Commands must be sent in
+    /// increasing sample order, since the receiver stops at the first command that's due
+    /// in the future. Returns [ChipError::QueueFull] if the receiver is falling behind.
+    pub fn send_at(&mut self, sample: usize, op: ChipOp) -> Result<(), ChipError> {
+        let shared = &self.shared;
+        let tail = shared.tail.load(Ordering::Relaxed);
+        let head = shared.head.load(Ordering::Acquire);
+        if tail.wrapping_sub(head) >= shared.slots.len() {
+            return Err(ChipError::QueueFull);
+        }
+        let slot = &shared.slots[tail % shared.slots.len()];
+        // Safety: the slot is outside the head..tail range, the receiver won't read it.
+        unsafe { (*slot.get()).write(Command { sample, op }) };
+        shared.tail.store(tail.wrapping_add(1), Ordering::Release);
+        Ok(())
+    }
+
+    /// Queues an operation to be applied a number of seconds after the chip's current time.
+    pub fn send_in(&mut self, seconds: f64, op: ChipOp) -> Result<(), ChipError> {
+        let delay = (seconds.max(0.0) * self.shared.sample_rate as f64) as usize;
+        self.send_at(self.now() + delay, op)
+    }
+
+    /// The last sample processed by the chip, updated by the receiver.
+    pub fn now(&self) -> usize {
+        self.shared.clock.load(Ordering::Acquire)
+    }
+
+    /// The sample rate used to convert seconds into samples.
+    pub fn sample_rate(&self) -> u32 {
+        self.shared.sample_rate
+    }
+
+    /// How many commands are waiting to be applied.
+    pub fn len(&self) -> usize {
+        self.shared.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// The consuming end of a command queue. Attach it with [SoundChip::set_commands()](crate::prelude::SoundChip::set_commands)
+/// so commands are applied sample accurately while processing.
+pub struct CommandReceiver {
+    shared: Arc<Shared>,
+}
+
+impl CommandReceiver {
+    /// Returns the next command if it's due at or before "sample".
+    pub fn next_due(&mut self, sample: usize) -> Option<Command> {
+        let shared = &self.shared;
+        let head = shared.head.load(Ordering::Relaxed);
+        let tail = shared.tail.load(Ordering::Acquire);
+        if head == tail {
+            return None;
+        }
+        let slot = &shared.slots[head % shared.slots.len()];
+        // Safety: the slot is inside the head..tail range, so it was initialized by the
+        // sender and won't be written again until head moves past it.
+        let command = unsafe { (*slot.get()).assume_init() };
+        if command.sample > sample {
+            return None;
+        }
+        shared.head.store(head.wrapping_add(1), Ordering::Release);
+        Some(command)
+    }
+
+    /// The sample at which the next queued command is due, if any.
+    pub fn next_sample(&self) -> Option<usize> {
+        let shared = &self.shared;
+        let head = shared.head.load(Ordering::Relaxed);
+        let tail = shared.tail.load(Ordering::Acquire);
+        if head == tail {
+            return None;
+        }
+        let slot = &shared.slots[head % shared.slots.len()];
+        // Safety: same as in next_due().
+        Some(unsafe { (*slot.get()).assume_init() }.sample)
+    }
+
+    /// Publishes the chip's current sample, read by [CommandSender::now()].
+    pub fn set_clock(&mut self, sample: usize) {
+        self.shared.clock.store(sample, Ordering::Release);
+    }
+
+    /// How many commands are waiting to be applied.
+    pub fn len(&self) -> usize {
+        self.shared.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl Shared {
+    fn len(&self) -> usize {
+        let tail = self.tail.load(Ordering::Acquire);
+        let head = self.head.load(Ordering::Acquire);
+        tail.wrapping_sub(head)
+    }
+}
+
+impl core::fmt::Debug for CommandSender {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_fmt(format_args!("CommandSender(len: {})", self.len()))
+    }
+}
+
+impl core::fmt::Debug for CommandReceiver {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_fmt(format_args!("CommandReceiver(len: {})", self.len()))
+    }
+}
+
+#[test]
+fn command_queue_order_and_capacity() {
+    let (mut sender, mut receiver) = command_queue(2, 100);
+    sender.send_at(10, ChipOp::PlayAllChannels).unwrap();
+    sender.send_at(20, ChipOp::StopAllChannels).unwrap();
+    assert_eq!(
+        sender.send(ChipOp::PlayAllChannels),
+        Err(ChipError::QueueFull)
+    );
+    assert_eq!(receiver.next_due(5), None);
+    assert_eq!(
+        receiver.next_due(10).map(|c| c.op),
+        Some(ChipOp::PlayAllChannels)
+    );
+    assert_eq!(receiver.next_due(15), None);
+
+    // Wraps around the ring buffer
+    receiver.set_clock(15);
+    sender.send_in(0.1, ChipOp::PlayAllChannels).unwrap();
+    assert_eq!(receiver.next_due(100).map(|c| c.sample), Some(20));
+    assert_eq!(receiver.next_due(100).map(|c| c.sample), Some(25));
+    assert!(receiver.is_empty());
+}
@@ -16,15 +16,21 @@ const SAFETY_EPSILON: f32 = f32::EPSILON * 2.0;
 
 /// A simple envelope that can be interpolated per knot..
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "EnvelopeData<T>"))]
 pub struct Envelope<T>
 where
     T: KnotValue,
 {
     pub knots: Vec<Knot<T>>,
     pub loop_kind: LoopKind,
+    #[cfg_attr(feature = "serde", serde(skip))]
     release: bool,
+    #[cfg_attr(feature = "serde", serde(skip))]
     release_time: Option<f32>,
+    #[cfg_attr(feature = "serde", serde(skip))]
     release_loop_pos: f32,
+    #[cfg_attr(feature = "serde", serde(skip))]
     head: usize,
 }
 
@@ -92,6 +98,32 @@ where
     }
 }
 
+// Deserialized envelopes go through this first, so their knots are sorted like in
+// Envelope::from, and an envelope without knots is rejected.
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+struct EnvelopeData<T>
+where
+    T: KnotValue,
+{
+    knots: Vec<Knot<T>>,
+    loop_kind: LoopKind,
+}
+
+#[cfg(feature = "serde")]
+impl<T> TryFrom<EnvelopeData<T>> for Envelope<T>
+where
+    T: KnotValue,
+{
+    type Error = crate::prelude::ChipError;
+
+    fn try_from(data: EnvelopeData<T>) -> Result<Self, Self::Error> {
+        if data.knots.is_empty() {
+            return Err(crate::prelude::ChipError::InvalidEnvelope);
+        }
+        Ok(Self::from(data.knots.as_slice()).set_loop(data.loop_kind))
+    }
+}
 
 impl<T> From<EnvelopePreset<T>> for Envelope<T>
 where
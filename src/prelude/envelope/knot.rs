@@ -4,6 +4,7 @@ use crate::prelude::KnotValue;
 
 /// A point in an envelope, with its associated time and value.
 #[derive(Debug, Clone, Copy, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Knot<T>
 where T:KnotValue
 {
@@ -13,6 +14,7 @@ where T:KnotValue
 }
 
 #[derive(Debug, Clone, Copy, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Interpolation {
     #[default]
     Linear,
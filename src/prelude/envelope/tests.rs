@@ -148,3 +148,20 @@ fn envelope_sort() {
         knot_time = knot.time;
     }
 }
+
+#[cfg(feature = "serde")]
+#[test]
+fn envelope_deserialize_validates_knots() {
+    use crate::prelude::Normal;
+    let json = r#"{"knots":[],"loop_kind":"None"}"#;
+    assert!(serde_json::from_str::<Envelope<Normal>>(json).is_err());
+
+    // Knots in reverse order are sorted.
+    let json = r#"{"knots":[
+        {"time":1.0,"value":0.0,"interpolation":"Linear"},
+        {"time":0.0,"value":1.0,"interpolation":"Linear"}
+    ],"loop_kind":"None"}"#;
+    let mut envelope = serde_json::from_str::<Envelope<Normal>>(json).unwrap();
+    assert_eq!(envelope.peek(0.0), 1.0);
+    assert_eq!(envelope.peek(0.5), 0.5);
+}
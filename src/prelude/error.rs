@@ -8,6 +8,13 @@ pub enum ChipError {
     InvalidWavetable,
     InvalidEnvelope,
     InvalidChannel,
+    InvalidGroup,
+    InvalidMml { position: usize },
+    InvalidMidi,
+    InvalidWav,
+    InvalidBinary,
+    UnsupportedVersion { version: u8 },
+    QueueFull,
 }
 
 impl fmt::Display for ChipError {
@@ -23,11 +30,32 @@ impl fmt::Display for ChipError {
                 write!(f, "Invalid Wavetable: sample out of -1.0 to 1.0 range")
             },
             ChipError::InvalidEnvelope => {
-                write!(f, "Invalid Envelope: knot value out of -1.0 to 1.0 range")
+                write!(f, "Invalid Envelope: no knots, or knot value out of -1.0 to 1.0 range")
             }
             ChipError::InvalidChannel => {
                 write!(f, "Invalid Channel: Channel Index not found")
             },
+            ChipError::InvalidGroup => {
+                write!(f, "Invalid Group: Group Index not found")
+            },
+            ChipError::InvalidMml { position } => {
+                write!(f, "Invalid MML: unexpected command at position {}", position)
+            },
+            ChipError::InvalidMidi => {
+                write!(f, "Invalid MIDI: malformed or truncated data")
+            },
+            ChipError::InvalidWav => {
+                write!(f, "Invalid WAV: malformed data or unsupported format")
+            },
+            ChipError::InvalidBinary => {
+                write!(f, "Invalid binary data: malformed or truncated")
+            },
+            ChipError::UnsupportedVersion { version } => {
+                write!(f, "Unsupported binary format version {}", version)
+            },
+            ChipError::QueueFull => {
+                write!(f, "Command queue is full")
+            },
         }
     }
 }
@@ -0,0 +1,144 @@
+use crate::{
+    prelude::{FilterKind, FilterPoles, SpecsFilter, SpecsOutput},
+    Vec,
+};
+use core::f32::consts::{FRAC_1_SQRT_2, PI};
+
+// Below this, a filter fed with silence is considered settled and its state is cleared.
+const SETTLE_THRESHOLD: f32 = 1e-7;
+
+// Runtime state for a SpecsOutput stage, with coefficients for the last used sample rate.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub(crate) struct OutputFilter {
+    specs: SpecsOutput,
+    sample_rate: u32,
+    stages: Vec<Stage>,
+}
+
+impl From<&SpecsOutput> for OutputFilter {
+    fn from(specs: &SpecsOutput) -> Self {
+        Self {
+            specs: specs.clone(),
+            sample_rate: 0,
+            stages: specs.filters.iter().map(|_| Stage::default()).collect(),
+        }
+    }
+}
+
+impl OutputFilter {
+    pub(crate) fn specs(&self) -> &SpecsOutput {
+        &self.specs
+    }
+
+    // True if processing silence would output silence.
+    #[inline(always)]
+    pub(crate) fn is_idle(&self) -> bool {
+        self.stages.iter().all(|stage| stage.state == [[0.0; 4]; 2])
+    }
+
+    pub(crate) fn process(&mut self, left: f32, right: f32, sample_rate: u32) -> (f32, f32) {
+        if self.stages.is_empty() {
+            return (left, right);
+        }
+        if sample_rate != self.sample_rate {
+            self.sample_rate = sample_rate;
+            for (stage, filter) in self.stages.iter_mut().zip(self.specs.filters.iter()) {
+                stage.coefs = coefficients(filter, sample_rate);
+            }
+        }
+        let (mut l, mut r) = (left, right);
+        for stage in &mut self.stages {
+            l = stage.process(l, 0);
+            r = stage.process(r, 1);
+        }
+        // Decaying filters never quite reach zero, which would keep silent channels rendering.
+        if left == 0.0 && right == 0.0 && l.abs() < SETTLE_THRESHOLD && r.abs() < SETTLE_THRESHOLD {
+            for stage in &mut self.stages {
+                stage.state = [[0.0; 4]; 2];
+            }
+        }
+        (l, r)
+    }
+}
+
+// A biquad in direct form I. One pole filters leave the second order coefficients at zero.
+#[derive(Debug, Clone, PartialEq, Default)]
+struct Stage {
+    // b0, b1, b2, a1, a2, normalized by a0.
+    coefs: [f32; 5],
+    // x1, x2, y1, y2 for each side.
+    state: [[f32; 4]; 2],
+}
+
+impl Stage {
+    #[inline(always)]
+    fn process(&mut self, x: f32, side: usize) -> f32 {
+        let [b0, b1, b2, a1, a2] = self.coefs;
+        let [x1, x2, y1, y2] = self.state[side];
+        let y = b0 * x + b1 * x1 + b2 * x2 - a1 * y1 - a2 * y2;
+        self.state[side] = [x, x1, y, y1];
+        y
+    }
+}
+
+fn coefficients(filter: &SpecsFilter, sample_rate: u32) -> [f32; 5] {
+    let sample_rate = sample_rate as f32;
+    let cutoff = filter.cutoff.clamp(1.0, sample_rate * 0.49);
+    let w0 = 2.0 * PI * cutoff / sample_rate;
+    match (filter.poles, filter.kind) {
+        (FilterPoles::One, FilterKind::LowPass) => {
+            let a = 1.0 - libm::expf(-w0);
+            [a, 0.0, 0.0, a - 1.0, 0.0]
+        }
+        (FilterPoles::One, FilterKind::HighPass) => {
+            let a = 1.0 / (1.0 + w0);
+            [a, -a, 0.0, -a, 0.0]
+        }
+        (FilterPoles::Two, kind) => {
+            let cos = libm::cosf(w0);
+            let alpha = libm::sinf(w0) / (2.0 * FRAC_1_SQRT_2);
+            let a0 = 1.0 + alpha;
+            let (b0, b1) = match kind {
+                FilterKind::LowPass => ((1.0 - cos) / 2.0, 1.0 - cos),
+                FilterKind::HighPass => ((1.0 + cos) / 2.0, -(1.0 + cos)),
+            };
+            [
+                b0 / a0,
+                b1 / a0,
+                b0 / a0,
+                -2.0 * cos / a0,
+                (1.0 - alpha) / a0,
+            ]
+        }
+    }
+}
+
+#[test]
+fn output_filters() {
+    let specs = |kind, poles| SpecsOutput {
+        filters: alloc::borrow::Cow::Owned(crate::Vec::from([SpecsFilter {
+            kind,
+            cutoff: 100.0,
+            poles,
+        }])),
+    };
+    for poles in [FilterPoles::One, FilterPoles::Two] {
+        // DC is removed by high-pass, and passes through low-pass.
+        let mut high = OutputFilter::from(&specs(FilterKind::HighPass, poles));
+        let mut low = OutputFilter::from(&specs(FilterKind::LowPass, poles));
+        let (mut high_out, mut low_out) = ((0.0, 0.0), (0.0, 0.0));
+        for _ in 0..44100 {
+            high_out = high.process(1.0, 0.5, 44100);
+            low_out = low.process(1.0, 0.5, 44100);
+        }
+        assert!(high_out.0.abs() < 0.001 && high_out.1.abs() < 0.001);
+        assert!((low_out.0 - 1.0).abs() < 0.001 && (low_out.1 - 0.5).abs() < 0.001);
+
+        // Eventually settles after the input goes silent.
+        assert!(!high.is_idle());
+        for _ in 0..44100 {
+            high.process(0.0, 0.0, 44100);
+        }
+        assert!(high.is_idle());
+    }
+}
@@ -0,0 +1,136 @@
+use super::{SpecsDivider, SpecsHardwareEnvelope};
+
+/// An AY-3-8910 style envelope generator. Runs at audio rate using one of 16 shapes, and
+/// its output level can replace the volume of any channel in the same [super::ChipGroup].
+#[derive(Debug, Clone, PartialEq)]
+pub struct HardwareEnvelope {
+    shape: u8,
+    frequency: f32,
+    steps: u8,
+    divider: Option<SpecsDivider>,
+    phase: f32,
+}
+
+impl Default for HardwareEnvelope {
+    fn default() -> Self {
+        Self {
+            shape: 0,
+            frequency: 1.0,
+            steps: 16,
+            divider: None,
+            phase: 0.0,
+        }
+    }
+}
+
+impl From<&SpecsHardwareEnvelope> for HardwareEnvelope {
+    fn from(specs: &SpecsHardwareEnvelope) -> Self {
+        Self {
+            steps: specs.steps.max(2),
+            divider: specs.divider,
+            ..Default::default()
+        }
+    }
+}
+
+impl HardwareEnvelope {
+    /// The current shape, from 0 to 15.
+    pub fn shape(&self) -> u8 {
+        self.shape
+    }
+
+    /// Sets the shape and restarts the envelope, like writing to the AY-3-8910 shape register.
+    /// Bits are "continue", "attack", "alternate" and "hold", from highest to lowest.
+    /// Shapes 8 (sawtooth down), 10 (triangle), 12 (sawtooth up) and 14 (triangle) loop forever.
+    pub fn set_shape(&mut self, shape: u8) {
+        self.shape = shape & 0x0F;
+        self.phase = 0.0;
+    }
+
+    /// How many ramps per second. Triangle shapes take two ramps for a complete cycle.
+    pub fn frequency(&self) -> f32 {
+        self.frequency
+    }
+
+    /// Sets how many ramps per second, quantized by the divider if there's one.
+    /// Set it to the channel's frequency to obtain a "buzzer" sound with sawtooth shapes.
+    pub fn set_frequency(&mut self, frequency: f32) {
+        self.frequency = if let Some(divider) = &self.divider {
+            divider.quantize(frequency)
+        } else {
+            frequency
+        };
+    }
+
+    /// Sets the frequency from a period register value. Has no effect without a divider.
+    pub fn set_period(&mut self, period: u32) {
+        if let Some(divider) = &self.divider {
+            self.frequency = divider.frequency(period);
+        }
+    }
+
+    /// Current output level, from 0.0 to 1.0, quantized to the number of steps.
+    pub fn level(&self) -> f32 {
+        let shape = self.shape;
+        let hold = shape & 0b0001 != 0;
+        let alternate = shape & 0b0010 != 0;
+        let attack = shape & 0b0100 != 0;
+        let cont = shape & 0b1000 != 0;
+        let ramp = libm::floorf(self.phase) as u32;
+        if ramp > 0 {
+            if !cont {
+                return 0.0;
+            }
+            if hold {
+                return if attack != alternate { 1.0 } else { 0.0 };
+            }
+        }
+        let rising = attack ^ (alternate && ramp % 2 == 1);
+        let max_step = (self.steps - 1) as f32;
+        let step = libm::floorf((self.phase - ramp as f32) * self.steps as f32).min(max_step);
+        let level = step / max_step;
+        if rising {
+            level
+        } else {
+            1.0 - level
+        }
+    }
+
+    /// Advances the envelope's internal timer.
+    pub fn advance(&mut self, delta_time: f32) {
+        self.phase += delta_time * self.frequency;
+        let looping = self.shape & 0b1001 == 0b1000;
+        if looping {
+            // Keeps the ramp parity for alternating shapes
+            if self.phase >= 2.0 {
+                self.phase %= 2.0;
+            }
+        } else if self.phase > 1.0 {
+            self.phase = 1.0;
+        }
+    }
+}
+
+#[test]
+fn hardware_envelope_shapes() {
+    let mut env = HardwareEnvelope::default();
+    env.set_frequency(4.0);
+    let mut levels = |shape: u8| {
+        env.set_shape(shape);
+        let mut result = [0.0; 4];
+        // Sample the middle of each ramp
+        env.advance(0.125);
+        for level in &mut result {
+            *level = env.level();
+            env.advance(0.25);
+        }
+        result.map(|level: f32| libm::roundf(level * 15.0) as u8)
+    };
+    assert_eq!(levels(0), [7, 0, 0, 0]);
+    assert_eq!(levels(4), [8, 0, 0, 0]);
+    assert_eq!(levels(8), [7, 7, 7, 7]);
+    assert_eq!(levels(10), [7, 8, 7, 8]);
+    assert_eq!(levels(11), [7, 15, 15, 15]);
+    assert_eq!(levels(13), [8, 15, 15, 15]);
+    assert_eq!(levels(15), [8, 0, 0, 0]);
+}
@@ -2,6 +2,7 @@ use super::Normal;
 
 /// Defines the envelope's looping behavior, if any.
 #[derive(Debug, Clone, Copy, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum LoopKind {
     #[default]
     /// No loop. The last knot's value will be maintained.
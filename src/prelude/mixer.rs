@@ -0,0 +1,239 @@
+use crate::{
+    math::compress_volume,
+    prelude::{MixMode, SpecsMixer},
+    Vec,
+};
+use alloc::collections::VecDeque;
+
+// Runtime state for the SoundChip's master mixer stage.
+#[derive(Debug, Clone)]
+pub(crate) struct Mixer {
+    specs: SpecsMixer,
+    sample_rate: u32,
+    volume: f32,
+    volume_target: f32,
+    volume_step: f32,
+    limiter: Option<Limiter>,
+}
+
+impl Mixer {
+    pub(crate) fn new(specs: SpecsMixer, sample_rate: u32) -> Self {
+        let mut result = Self {
+            specs,
+            sample_rate,
+            volume: 1.0,
+            volume_target: 1.0,
+            volume_step: 0.0,
+            limiter: None,
+        };
+        result.set_sample_rate(sample_rate);
+        result
+    }
+
+    // Replaces the specs, keeping the master volume.
+    pub(crate) fn set_specs(&mut self, specs: SpecsMixer, sample_rate: u32) {
+        self.specs = specs;
+        self.set_sample_rate(sample_rate);
+    }
+
+    pub(crate) fn specs(&self) -> &SpecsMixer {
+        &self.specs
+    }
+
+    // The volume the master volume is ramping to.
+    pub(crate) fn target_volume(&self) -> f32 {
+        self.volume_target
+    }
+
+    pub(crate) fn set_volume(&mut self, volume: f32) {
+        self.volume_target = volume.max(0.0);
+        let ramp_samples = self.specs.volume_ramp * self.sample_rate as f32;
+        if ramp_samples < 1.0 {
+            self.volume = self.volume_target;
+            self.volume_step = 0.0;
+        } else {
+            self.volume_step = (self.volume_target - self.volume) / ramp_samples;
+        }
+    }
+
+    // Sample rate dependent state, rebuilt if the chip's sample rate changes.
+    fn set_sample_rate(&mut self, sample_rate: u32) {
+        self.sample_rate = sample_rate;
+        self.limiter = match self.specs.mode {
+            MixMode::Limiter {
+                threshold,
+                lookahead,
+                release,
+            } => Some(Limiter::new(threshold, lookahead, release, sample_rate)),
+            _ => None,
+        };
+        self.set_volume(self.volume_target);
+    }
+
+    // Mixes down summed channel values in place, to the -1.0 to 1.0 range.
+    pub(crate) fn process(
+        &mut self,
+        left: &mut [f32],
+        right: &mut [f32],
+        channel_count: usize,
+        sample_rate: u32,
+    ) {
+        if sample_rate != self.sample_rate {
+            self.set_sample_rate(sample_rate);
+        }
+        let gain = if self.specs.auto_gain && channel_count > 1 {
+            1.0 / libm::sqrtf(channel_count as f32)
+        } else {
+            1.0
+        };
+        for (left, right) in left.iter_mut().zip(right.iter_mut()) {
+            if self.volume != self.volume_target {
+                self.volume += self.volume_step;
+                let reached = if self.volume_step > 0.0 {
+                    self.volume >= self.volume_target
+                } else {
+                    self.volume <= self.volume_target
+                };
+                if reached {
+                    self.volume = self.volume_target;
+                }
+            }
+            let l = *left * gain * self.volume;
+            let r = *right * gain * self.volume;
+            let (l, r) = match self.specs.mode {
+                MixMode::Linear { headroom } => (l / headroom, r / headroom),
+                MixMode::Sine { compression } => (
+                    compress_volume(l, compression),
+                    compress_volume(r, compression),
+                ),
+                MixMode::Tanh { drive } => (libm::tanhf(l * drive), libm::tanhf(r * drive)),
+                MixMode::Cubic => (cubic(l), cubic(r)),
+                MixMode::Limiter { .. } => match &mut self.limiter {
+                    Some(limiter) => limiter.process(l, r),
+                    None => (l, r),
+                },
+            };
+            *left = l.clamp(-1.0, 1.0);
+            *right = r.clamp(-1.0, 1.0);
+        }
+    }
+}
+
+fn cubic(value: f32) -> f32 {
+    let x = value.clamp(-1.0, 1.0);
+    (1.5 * x) - (0.5 * x * x * x)
+}
+
+// Stereo linked look-ahead limiter. The signal is delayed while a sliding window finds
+// the lowest gain required by any upcoming sample, so the gain can ramp down in time.
+#[derive(Debug, Clone)]
+struct Limiter {
+    threshold: f32,
+    attack_step: f32,
+    release_coef: f32,
+    gain: f32,
+    delay: Vec<(f32, f32)>,
+    position: usize,
+    // Increasing required gains with their sample counter, the front is the window minimum.
+    window: VecDeque<(usize, f32)>,
+    counter: usize,
+}
+
+impl Limiter {
+    fn new(threshold: f32, lookahead: f32, release: f32, sample_rate: u32) -> Self {
+        let length = (lookahead.max(0.0) * sample_rate as f32) as usize;
+        let release_samples = release * sample_rate as f32;
+        Self {
+            threshold: threshold.clamp(0.0, 1.0),
+            attack_step: 1.0 / length.max(1) as f32,
+            release_coef: if release_samples > 1.0 {
+                1.0 - libm::expf(-1.0 / release_samples)
+            } else {
+                1.0
+            },
+            gain: 1.0,
+            delay: (0..length).map(|_| (0.0, 0.0)).collect(),
+            position: 0,
+            // Never grows past the window length, so it never reallocates.
+            window: VecDeque::with_capacity(length + 1),
+            counter: 0,
+        }
+    }
+
+    fn process(&mut self, left: f32, right: f32) -> (f32, f32) {
+        let peak = left.abs().max(right.abs());
+        let required = if peak > self.threshold {
+            self.threshold / peak
+        } else {
+            1.0
+        };
+        while self
+            .window
+            .back()
+            .is_some_and(|(_, gain)| *gain >= required)
+        {
+            self.window.pop_back();
+        }
+        self.window.push_back((self.counter, required));
+        let length = self.delay.len();
+        while self
+            .window
+            .front()
+            .is_some_and(|(counter, _)| counter + length < self.counter)
+        {
+            self.window.pop_front();
+        }
+        self.counter += 1;
+
+        let target = self.window.front().map_or(1.0, |(_, gain)| *gain);
+        if target < self.gain {
+            self.gain = (self.gain - self.attack_step).max(target);
+        } else {
+            self.gain += (target - self.gain) * self.release_coef;
+        }
+
+        if length == 0 {
+            return (left * self.gain, right * self.gain);
+        }
+        let (delayed_left, delayed_right) = self.delay[self.position];
+        self.delay[self.position] = (left, right);
+        self.position = (self.position + 1) % length;
+        (delayed_left * self.gain, delayed_right * self.gain)
+    }
+}
+
+#[test]
+fn mixer_modes() {
+    let render = |mode: MixMode, input: &[f32]| {
+        let specs = SpecsMixer {
+            mode,
+            ..Default::default()
+        };
+        let mut mixer = Mixer::new(specs, 1000);
+        let mut left: Vec<f32> = input.to_vec();
+        let mut right = left.clone();
+        mixer.process(&mut left, &mut right, 1, 1000);
+        left
+    };
+    let input = [0.1, 0.5, 2.0, -2.0];
+    assert_eq!(
+        render(MixMode::Linear { headroom: 2.0 }, &input),
+        [0.05, 0.25, 1.0, -1.0]
+    );
+    let cubic = render(MixMode::Cubic, &input);
+    assert!(cubic[0] > 0.1 && cubic[2] == 1.0 && cubic[3] == -1.0);
+
+    // The limiter reaches the required gain before the peak leaves the delay line.
+    let mut input = [0.5; 40];
+    input[20] = 2.0;
+    let limiter = MixMode::Limiter {
+        threshold: 0.8,
+        lookahead: 0.01,
+        release: 0.005,
+    };
+    let output = render(limiter, &input);
+    assert_eq!(output[..10], [0.0; 10]);
+    assert!((output[30] - 0.8).abs() < 0.0001);
+    assert!(output.iter().all(|value| *value <= 0.8 + 0.0001));
+    assert!(output[39] > output[31]);
+}
@@ -0,0 +1,151 @@
+/// Sample types a [SoundChip](crate::prelude::SoundChip) can render to.
+pub trait OutputSample: Copy {
+    /// The largest positive value for integer formats, which are quantized and can be
+    /// dithered. None for floating point formats, which keep the -1.0 to 1.0 range.
+    const SCALE: Option<f32>;
+
+    /// Converts a value already multiplied by SCALE and clamped to its range. Fractions
+    /// are truncated.
+    fn from_scaled(value: f32) -> Self;
+}
+
+impl OutputSample for f32 {
+    const SCALE: Option<f32> = None;
+    fn from_scaled(value: f32) -> Self {
+        value
+    }
+}
+
+/// Slightly under i16::MAX, for compatibility with the original output.
+impl OutputSample for i16 {
+    const SCALE: Option<f32> = Some((i16::MAX - 1) as f32);
+    fn from_scaled(value: f32) -> Self {
+        value as i16
+    }
+}
+
+/// Unsigned, centered at 128. Useful for 8 bit DACs.
+impl OutputSample for u8 {
+    const SCALE: Option<f32> = Some(127.0);
+    fn from_scaled(value: f32) -> Self {
+        (value + 128.0) as u8
+    }
+}
+
+/// A 24 bit sample stored in the lower bits of an i32, sign extended.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct I24(pub i32);
+
+impl OutputSample for I24 {
+    const SCALE: Option<f32> = Some(8_388_607.0);
+    fn from_scaled(value: f32) -> Self {
+        I24(value as i32)
+    }
+}
+
+/// Dithering applied when quantizing to integer formats. Ignored by float formats.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum Dither {
+    /// Plain truncation.
+    #[default]
+    None,
+    /// Triangular noise of up to one step, which turns quantization distortion into a
+    /// constant, low level hiss.
+    Tpdf,
+    /// TPDF dither with first order noise shaping, which moves most of the hiss to the
+    /// higher frequencies where it's less audible.
+    TpdfShaped,
+}
+
+/// How channels are laid out in the output buffer.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum OutputLayout {
+    /// Interleaved left and right samples.
+    #[default]
+    Stereo,
+    /// Left and right mixed down into a single sample.
+    Mono,
+}
+
+/// Output settings for [SoundChip::render_into_format()](crate::prelude::SoundChip::render_into_format).
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct OutputOptions {
+    pub layout: OutputLayout,
+    pub dither: Dither,
+}
+
+// Random source and shaping error for each side, kept between render calls so the noise
+// doesn't restart on every buffer.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct DitherState {
+    seed: u32,
+    error: [f32; 2],
+}
+
+impl Default for DitherState {
+    fn default() -> Self {
+        Self {
+            seed: 0x5EED_1234,
+            error: [0.0; 2],
+        }
+    }
+}
+
+impl DitherState {
+    // Converts a value in the -1.0 to 1.0 range. "side" is 0 for left (or mono), 1 for right.
+    #[inline(always)]
+    pub(crate) fn convert<T: OutputSample>(
+        &mut self,
+        value: f32,
+        side: usize,
+        dither: Dither,
+    ) -> T {
+        let Some(scale) = T::SCALE else {
+            return T::from_scaled(value);
+        };
+        let scaled = value * scale;
+        let quantized = match dither {
+            Dither::None => scaled,
+            Dither::Tpdf => libm::roundf(scaled + self.tpdf()),
+            Dither::TpdfShaped => {
+                let target = scaled - self.error[side];
+                let quantized = libm::roundf(target + self.tpdf());
+                self.error[side] = (quantized - target).clamp(-2.0, 2.0);
+                quantized
+            }
+        };
+        T::from_scaled(quantized.clamp(-scale, scale))
+    }
+
+    // Triangular distribution from -1.0 to 1.0.
+    fn tpdf(&mut self) -> f32 {
+        self.next_f32() - self.next_f32()
+    }
+
+    // Xorshift, since consecutive values from the LFSR in the rng module are too correlated
+    // to produce an even distribution.
+    fn next_f32(&mut self) -> f32 {
+        self.seed ^= self.seed << 13;
+        self.seed ^= self.seed >> 17;
+        self.seed ^= self.seed << 5;
+        (self.seed >> 8) as f32 / (1 << 24) as f32
+    }
+}
+
+#[test]
+fn output_formats() {
+    let mut state = DitherState::default();
+    assert_eq!(state.convert::<f32>(0.25, 0, Dither::Tpdf), 0.25);
+    assert_eq!(state.convert::<i16>(1.0, 0, Dither::None), i16::MAX - 1);
+    assert_eq!(state.convert::<u8>(0.0, 0, Dither::None), 128);
+    assert_eq!(state.convert::<u8>(-1.0, 0, Dither::None), 1);
+    assert_eq!(state.convert::<I24>(-1.0, 0, Dither::None), I24(-8_388_607));
+
+    // Dithered silence averages to zero, within a couple of steps.
+    for dither in [Dither::Tpdf, Dither::TpdfShaped] {
+        let samples: crate::Vec<u8> = (0..1000).map(|_| state.convert(0.0, 1, dither)).collect();
+        assert!(samples.iter().all(|s| (126..=130).contains(s)));
+        let average = samples.iter().map(|s| *s as f32).sum::<f32>() / 1000.0;
+        assert!((average - 128.0).abs() < 0.1);
+    }
+}
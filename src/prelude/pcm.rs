@@ -0,0 +1,114 @@
+use crate::{math::note_to_frequency, Vec};
+
+/// How a [PcmSample] behaves when playback reaches its end.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum PcmLoop {
+    #[default]
+    /// Plays once, then stops the channel.
+    OneShot,
+    /// Plays from the beginning, then repeats between "start" and "end" (sample indices,
+    /// end is exclusive) indefinitely.
+    Forward { start: usize, end: usize },
+    /// Same as Forward while the channel isn't released. After [super::Channel::release()]
+    /// it exits the loop and plays the rest of the sample (the "tail"), then stops.
+    ForwardRelease { start: usize, end: usize },
+}
+
+/// An arbitrarily long buffer of sampled sound, played back by a [super::Channel] using
+/// [super::Channel::set_pcm()]. Playback speed follows the channel's pitch relative to the
+/// root note, and the chip's wavetable steps still apply for bit-depth reduction.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PcmSample {
+    /// Sample values, from -1.0 to 1.0.
+    pub data: Vec<f32>,
+    /// The sample rate the data was recorded at, in Hertz.
+    pub sample_rate: f32,
+    /// MIDI note (C4 = 60) that plays the sample at its original speed.
+    pub root_note: f32,
+    /// Looping behavior.
+    pub loop_kind: PcmLoop,
+}
+
+impl Default for PcmSample {
+    fn default() -> Self {
+        Self {
+            data: Vec::new(),
+            sample_rate: 44100.0,
+            root_note: 60.0,
+            loop_kind: PcmLoop::OneShot,
+        }
+    }
+}
+
+impl PcmSample {
+    /// A one-shot sample with root note C4.
+    pub fn new(data: Vec<f32>, sample_rate: f32) -> Self {
+        Self {
+            data,
+            sample_rate,
+            ..Default::default()
+        }
+    }
+
+    /// Creates a one-shot sample from signed 16 bit values.
+    pub fn from_i16(data: &[i16], sample_rate: f32) -> Self {
+        let data = data.iter().map(|value| *value as f32 / 32768.0).collect();
+        Self::new(data, sample_rate)
+    }
+
+    /// Returns the same sample with a new root note.
+    pub fn with_root_note(mut self, root_note: f32) -> Self {
+        self.root_note = root_note;
+        self
+    }
+
+    /// Returns the same sample with a new loop kind.
+    pub fn with_loop(mut self, loop_kind: PcmLoop) -> Self {
+        self.loop_kind = loop_kind;
+        self
+    }
+
+    /// How many data samples to advance per second when played at "frequency".
+    pub(crate) fn rate_at(&self, frequency: f32) -> f32 {
+        self.sample_rate * (frequency / note_to_frequency(self.root_note))
+    }
+
+    /// Wraps a playback position according to the loop kind. Returns None if the sample
+    /// has ended.
+    pub(crate) fn wrap(&self, position: f32, released: bool) -> Option<f32> {
+        let len = self.data.len();
+        let loop_range = match self.loop_kind {
+            PcmLoop::OneShot => None,
+            PcmLoop::Forward { start, end } => Some((start, end)),
+            PcmLoop::ForwardRelease { start, end } => (!released).then_some((start, end)),
+        };
+        if let Some((start, end)) = loop_range {
+            let end = end.min(len);
+            if start < end && position >= end as f32 {
+                let loop_len = (end - start) as f32;
+                return Some(start as f32 + ((position - start as f32) % loop_len));
+            }
+        }
+        if position < len as f32 {
+            Some(position)
+        } else {
+            None
+        }
+    }
+}
+
+#[test]
+fn pcm_loop_wrapping() {
+    let sample = PcmSample::new(Vec::from([0.0; 10]), 100.0);
+    assert_eq!(sample.wrap(9.5, false), Some(9.5));
+    assert_eq!(sample.wrap(10.0, false), None);
+
+    let sample = sample.with_loop(PcmLoop::ForwardRelease { start: 4, end: 8 });
+    assert_eq!(sample.wrap(8.5, false), Some(4.5));
+    assert_eq!(sample.wrap(13.0, false), Some(5.0));
+    // Once released, the tail plays until the end.
+    assert_eq!(sample.wrap(8.5, true), Some(8.5));
+    assert_eq!(sample.wrap(10.0, true), None);
+
+    assert!((sample.rate_at(note_to_frequency(72.0)) - 200.0).abs() < 0.01);
+}
@@ -5,6 +5,7 @@ use super::{Envelope, Normal, NormalSigned, Tremolo, Vibratto};
 /// A single struct containing all public properties a sound can have,
 /// such as volume, pitch, envelopes, etc.
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Sound {
     /// Base volume excluding envelopes.
     pub volume: f32,
@@ -40,3 +41,29 @@ impl Default for Sound {
         }
     }
 }
+
+#[cfg(feature = "serde")]
+#[test]
+fn sound_serde_round_trip() {
+    use crate::{presets::*, prelude::*};
+    let sound = Sound {
+        waveform: Some(Envelope::from(KNOTS_WAVE_SQUARE)),
+        pitch_env: Some(Envelope::from(KNOTS_PITCH_DOWN).set_loop(LoopKind::Repeat)),
+        tremolo: Some(TREMOLO_SUBTLE),
+        ..Default::default()
+    };
+    let json = serde_json::to_string(&sound).unwrap();
+    assert_eq!(serde_json::from_str::<Sound>(&json).unwrap(), sound);
+
+    // Static preset knots are deserialized into owned ones.
+    let json = serde_json::to_string(&SPEC_CHIP_PSG_NOISE).unwrap();
+    assert_eq!(serde_json::from_str::<SpecsChip>(&json).unwrap(), SPEC_CHIP_PSG_NOISE);
+
+    // Normalized values are readable and survive repeated round trips.
+    let value = NormalSigned::NEG_ONE;
+    let json = serde_json::to_string(&value).unwrap();
+    assert_eq!(serde_json::from_str::<NormalSigned>(&json).unwrap(), value);
+    let value = Normal::from(0.3);
+    let json = serde_json::to_string(&value).unwrap();
+    assert_eq!(serde_json::from_str::<Normal>(&json).unwrap(), value);
+}
@@ -1,7 +1,9 @@
-use crate::{math::*, prelude::*, presets::*, Vec};
+use crate::{prelude::*, presets::*, Vec};
 
-const MAX_I16: f32 = (i16::MAX - 1) as f32;
-const MIX_COMPRESSION: f32 = 1.6;
+// Maximum number of samples rendered at once, sized for stack buffers.
+const BLOCK_SIZE: usize = 256;
+// Prevents rounding errors from pushing scheduled events one sample late.
+const TIME_EPSILON: f64 = 1e-6;
 
 /// Contains multiple sound channels, and can render and mix them all at once.
 pub struct SoundChip {
@@ -10,6 +12,13 @@ pub struct SoundChip {
     pub sample_rate: u32,
     /// Vector containing sound channels. You can directly manipulate it to add/remove channels.
     pub channels: Vec<Channel>,
+    /// Resources shared by channels of the same physical chip, like the AY-3-8910's
+    /// envelope generator. Use [SoundChip::set_group()] to assign channels to a group.
+    pub groups: Vec<ChipGroup>,
+    commands: Option<CommandReceiver>,
+    scheduled: Vec<ScheduledEvent>,
+    dither: DitherState,
+    mixer: Mixer,
     sample_head: usize,
     last_sample_time: f64,
 }
@@ -18,6 +27,11 @@ impl Default for SoundChip {
     fn default() -> Self {
         Self {
             channels: Vec::new(),
+            groups: Vec::new(),
+            commands: None,
+            scheduled: Vec::new(),
+            dither: DitherState::default(),
+            mixer: Mixer::new(SpecsMixer::default(), 44100),
             sample_rate: 44100,
             // auto_prevent_clipping: true,
             sample_head: 0,
@@ -38,7 +52,7 @@ impl SoundChip {
     /// Creates a SoundChip configured to replicate an AY-3-8910 sound chip with 3 square wave channels.
     pub fn new_msx(sample_rate: u32) -> Self {
         // println!("New MSX sound chip");
-        Self {
+        let mut result = Self {
             channels: (0..3)
                 .map(|i| match i {
                     0 => Channel::from(SPEC_CHIP_PSG_NOISE),
@@ -47,14 +61,19 @@ impl SoundChip {
                 .collect(),
             sample_rate,
             ..Default::default()
+        };
+        let psg = result.add_group(ChipGroup::from(&SPEC_CHIP_PSG_NOISE));
+        for channel in &mut result.channels {
+            channel.set_group(Some(psg));
         }
+        result
     }
 
     /// Creates a SoundChip configured to replicate an AY-3-8910 sound chip with 3 square wave channels plus
     /// an SCC chip with 5 wavetable channels (32 byte samples).
     pub fn new_msx_scc(sample_rate: u32) -> Self {
         // println!("New MSX-SCC sound chip");
-        Self {
+        let mut result = Self {
             channels: (0..8)
                 .map(|i| match i {
                     0 => Channel::from(SPEC_CHIP_PSG_NOISE),
@@ -64,13 +83,19 @@ impl SoundChip {
                 .collect(),
             sample_rate,
             ..Default::default()
+        };
+        let psg = result.add_group(ChipGroup::from(&SPEC_CHIP_PSG_NOISE));
+        for channel in &mut result.channels[0..3] {
+            channel.set_group(Some(psg));
         }
+        result
     }
 
-    /// Creates a SoundChip configured to mimic an NES APU.
+    /// Creates a SoundChip configured to mimic an NES APU. The pulse channels and the
+    /// remaining channels are in separate groups, mixed through the APU's two non-linear tables.
     pub fn new_nes(sample_rate: u32) -> Self {
         // println!("New MSX-SCC sound chip");
-        Self {
+        let mut result = Self {
             channels: (0..8)
                 .map(|i| match i {
                     0 | 1 => Channel::from(SPEC_CHIP_NES_SQUARE),
@@ -81,7 +106,13 @@ impl SoundChip {
                 .collect(),
             sample_rate,
             ..Default::default()
+        };
+        let pulse = result.add_group(ChipGroup::from(&SPEC_CHIP_NES_SQUARE));
+        let tnd = result.add_group(ChipGroup::from(&SPEC_CHIP_NES_TRIANGLE));
+        for (index, channel) in result.channels.iter_mut().enumerate() {
+            channel.set_group(Some(if index < 2 { pulse } else { tnd }));
         }
+        result
     }
 
     /// Adds new channel from specs, returns its index.
@@ -91,6 +122,27 @@ impl SoundChip {
         index
     }
 
+    /// Adds a new chip group, returns its index.
+    pub fn add_group(&mut self, group: ChipGroup) -> usize {
+        let index = self.groups.len();
+        self.groups.push(group);
+        index
+    }
+
+    /// Assigns a channel to a chip group (or to no group, if None), allowing it to use the
+    /// group's shared resources. Returns an error if the channel or group don't exist.
+    pub fn set_group(&mut self, channel: usize, group: Option<usize>) -> Result<(), ChipError> {
+        if group.is_some_and(|index| index >= self.groups.len()) {
+            return Err(ChipError::InvalidGroup);
+        }
+        let channel = self
+            .channels
+            .get_mut(channel)
+            .ok_or(ChipError::InvalidChannel)?;
+        channel.set_group(group);
+        Ok(())
+    }
+
     /// Removes channel, returns an empty result if successful, an error
     /// if the operation failed.
     pub fn remove_channel(&mut self, index:usize) -> Result<(), ChipError> {
@@ -122,6 +174,80 @@ impl SoundChip {
         }
     }
 
+    /// Replaces the master mixer stage, which turns the sum of all channels into the
+    /// final output. The master volume is kept.
+    pub fn set_mixer(&mut self, specs: SpecsMixer) {
+        self.mixer.set_specs(specs, self.sample_rate);
+    }
+
+    /// The current master mixer specs.
+    pub fn mixer(&self) -> &SpecsMixer {
+        self.mixer.specs()
+    }
+
+    /// Sets the master volume, applied before the mixer's clipping stage. Changes ramp
+    /// smoothly over the mixer's "volume_ramp" time.
+    pub fn set_master_volume(&mut self, volume: f32) {
+        self.mixer.set_volume(volume);
+    }
+
+    /// The master volume, or the volume it's ramping to.
+    pub fn master_volume(&self) -> f32 {
+        self.mixer.target_volume()
+    }
+
+    /// Attaches the receiving end of a [command_queue()], or detaches it if None. Queued
+    /// commands are applied at the start of the sample they're due.
+    pub fn set_commands(&mut self, receiver: Option<CommandReceiver>) {
+        self.commands = receiver;
+    }
+
+    /// Applies an operation immediately.
+    pub fn apply(&mut self, op: ChipOp) {
+        match op {
+            ChipOp::PlayAllChannels => {
+                for channel in &mut self.channels {
+                    channel.play();
+                }
+            }
+            ChipOp::StopAllChannels => self.channel_stop_all(),
+            ChipOp::Channel { index, op } => {
+                if let Some(channel) = self.channels.get_mut(index) {
+                    op.apply(channel);
+                }
+            }
+        }
+    }
+
+    /// Schedules an event to be applied to a channel at the first sample where
+    /// [SoundChip::time()] reaches "at_time", regardless of how samples are requested.
+    /// Events in the past are applied on the next processed sample, and events scheduled
+    /// for the same sample are applied in the order they were scheduled. Events for
+    /// invalid channels are ignored.
+    pub fn schedule(&mut self, at_time: f64, channel: usize, event: impl Into<ChannelEvent>) {
+        let sample = libm::ceil(at_time * self.sample_rate as f64 - TIME_EPSILON).max(0.0) as usize;
+        // Kept in descending order, so due events are popped from the end.
+        let index = self.scheduled.partition_point(|item| item.sample > sample);
+        self.scheduled.insert(
+            index,
+            ScheduledEvent {
+                sample,
+                channel,
+                event: event.into(),
+            },
+        );
+    }
+
+    /// How many scheduled events haven't been applied yet.
+    pub fn scheduled_len(&self) -> usize {
+        self.scheduled.len()
+    }
+
+    /// Removes all pending scheduled events.
+    pub fn clear_schedule(&mut self) {
+        self.scheduled.clear();
+    }
+
     /// Renders a given number of samples on demand. Normally the requested sample count
     /// should be 'sample_rate * elapsed_time';
     pub fn iter(&mut self, sample_count: usize) -> SoundChipIter {
@@ -134,24 +260,164 @@ impl SoundChip {
 
     /// Process a single sample, advancing internal timer.
     pub fn process_sample(&mut self) -> Sample<i16> {
-        let mut left: f32 = 0.0;
-        let mut right: f32 = 0.0;
+        self.process_sample_as(Dither::None)
+    }
+
+    /// Process a single sample in any output format, advancing internal timer.
+    pub fn process_sample_as<T: OutputSample>(&mut self, dither: Dither) -> Sample<T> {
+        let mut left = [0.0];
+        let mut right = [0.0];
+        self.render_span(&mut left, &mut right);
+        Sample {
+            left: self.dither.convert(left[0], 0, dither),
+            right: self.dither.convert(right[0], 1, dither),
+        }
+    }
+
+    /// Renders interleaved stereo samples into a caller provided buffer, without allocating.
+    /// Values are in the -1.0 to 1.0 range. A trailing odd value is left untouched.
+    pub fn render_into(&mut self, buffer: &mut [f32]) {
+        self.render_into_format(buffer, OutputOptions::default());
+    }
+
+    /// Same as [SoundChip::render_into()], with i16 samples.
+    pub fn render_into_i16(&mut self, buffer: &mut [i16]) {
+        self.render_into_format(buffer, OutputOptions::default());
+    }
+
+    /// Renders into a buffer of any output format, either interleaved stereo or mixed down
+    /// to mono, optionally dithering when quantizing to integer formats.
+    pub fn render_into_format<T: OutputSample>(&mut self, buffer: &mut [T], options: OutputOptions) {
+        // Taken out so the renderer can borrow it while also borrowing self.
+        let mut state = core::mem::take(&mut self.dither);
+        let dither = options.dither;
+        match options.layout {
+            OutputLayout::Stereo => self.render_frames(buffer.len() / 2, |i, left, right| {
+                buffer[i * 2] = state.convert(left, 0, dither);
+                buffer[i * 2 + 1] = state.convert(right, 1, dither);
+            }),
+            OutputLayout::Mono => self.render_frames(buffer.len(), |i, left, right| {
+                buffer[i] = state.convert((left + right) * 0.5, 0, dither);
+            }),
+        }
+        self.dither = state;
+    }
+
+    /// Renders stereo samples into separate left and right buffers, in the -1.0 to 1.0
+    /// range. If the lengths differ, only the shortest length is rendered.
+    pub fn render_into_planar(&mut self, left: &mut [f32], right: &mut [f32]) {
+        self.render_into_planar_format(left, right, Dither::None);
+    }
+
+    /// Same as [SoundChip::render_into_planar()], with i16 samples.
+    pub fn render_into_planar_i16(&mut self, left: &mut [i16], right: &mut [i16]) {
+        self.render_into_planar_format(left, right, Dither::None);
+    }
+
+    /// Same as [SoundChip::render_into_planar()], with any output format.
+    pub fn render_into_planar_format<T: OutputSample>(
+        &mut self,
+        left: &mut [T],
+        right: &mut [T],
+        dither: Dither,
+    ) {
+        let frames = left.len().min(right.len());
+        let mut state = core::mem::take(&mut self.dither);
+        self.render_frames(frames, |i, l, r| {
+            left[i] = state.convert(l, 0, dither);
+            right[i] = state.convert(r, 1, dither);
+        });
+        self.dither = state;
+    }
+
+    // Renders in spans of up to BLOCK_SIZE samples, split wherever a command or scheduled
+    // event is due so they're still applied at the exact sample.
+    fn render_frames(&mut self, frames: usize, mut write: impl FnMut(usize, f32, f32)) {
+        let mut left = [0.0; BLOCK_SIZE];
+        let mut right = [0.0; BLOCK_SIZE];
+        let mut done = 0;
+        while done < frames {
+            let mut len = (frames - done).min(BLOCK_SIZE);
+            let next_command = self.commands.as_ref().and_then(|c| c.next_sample());
+            let next_event = self.scheduled.last().map(|item| item.sample);
+            for next in [next_command, next_event].into_iter().flatten() {
+                if next > self.sample_head {
+                    len = len.min(next - self.sample_head);
+                }
+            }
+            self.render_span(&mut left[..len], &mut right[..len]);
+            for i in 0..len {
+                write(done + i, left[i], right[i]);
+            }
+            done += len;
+        }
+    }
+
+    // Applies due events, then renders and mixes a span of samples with no events in it.
+    fn render_span(&mut self, left: &mut [f32], right: &mut [f32]) {
+        if let Some(mut commands) = self.commands.take() {
+            commands.set_clock(self.sample_head);
+            while let Some(command) = commands.next_due(self.sample_head) {
+                self.apply(command.op);
+            }
+            self.commands = Some(commands);
+        }
 
-        let time = self.sample_head as f64 / self.sample_rate as f64;
-        let delta_time = time - self.last_sample_time;
-        self.last_sample_time = time;
+        while let Some(item) = self.scheduled.last() {
+            if item.sample > self.sample_head {
+                break;
+            }
+            if let Some(item) = self.scheduled.pop() {
+                if let Some(channel) = self.channels.get_mut(item.channel) {
+                    item.event.apply(channel);
+                }
+            }
+        }
+
+        left.fill(0.0);
+        right.fill(0.0);
+
+        let mut deltas = [0.0; BLOCK_SIZE];
+        let deltas = &mut deltas[..left.len()];
+        for (i, delta_time) in deltas.iter_mut().enumerate() {
+            let time = (self.sample_head + i) as f64 / self.sample_rate as f64;
+            *delta_time = (time - self.last_sample_time) as f32; // delta will be always tiny, f32 is fine(?)
+            self.last_sample_time = time;
+        }
 
+        // Channels outside of a group render the whole span at once. They're still summed
+        // in channel order and before the groups, same as sample by sample.
+        let group_count = self.groups.len();
         for channel in &mut self.channels {
-            let sample = channel.sample(delta_time as f32); // delta will be always tiny, f32 is fine(?)
-            left += sample.left;
-            right += sample.right;
+            if channel.group().is_none_or(|index| index >= group_count) {
+                channel.render_span(deltas, left, right, self.sample_rate);
+            }
         }
 
-        self.sample_head += 1;
-        Sample {
-            left: (compress_volume(left, MIX_COMPRESSION).clamp(-1.0, 1.0) * MAX_I16) as i16,
-            right: (compress_volume(right, MIX_COMPRESSION).clamp(-1.0, 1.0) * MAX_I16) as i16,
+        // Sample by sample, so channels sharing a group stay in lockstep with it.
+        for (i, (left, right)) in left.iter_mut().zip(right.iter_mut()).enumerate() {
+            let delta_time = deltas[i];
+            for channel in &mut self.channels {
+                if channel.is_silent() {
+                    continue;
+                }
+                // Grouped channels are filtered together by the group's output stage.
+                if let Some(group) = channel.group().and_then(|index| self.groups.get_mut(index)) {
+                    let sample = channel.sample(delta_time, Some(&mut *group));
+                    group.add_output(sample.left, sample.right);
+                }
+            }
+
+            for group in &mut self.groups {
+                let (group_left, group_right) = group.take_output(self.sample_rate);
+                *left += group_left;
+                *right += group_right;
+                group.advance(delta_time);
+            }
         }
+
+        self.mixer.process(left, right, self.channels.len(), self.sample_rate);
+        self.sample_head += left.len();
     }
 
     /// This is the only f64 value, calculated from an internal usize integer.
@@ -160,8 +426,39 @@ impl SoundChip {
         self.sample_head as f64 / self.sample_rate as f64
     }
 
-    /// Stops all channels and resets all timers
+    /// Captures the complete runtime state of every channel and chip group, including
+    /// oscillator phases, envelope positions and noise generators. Restoring it later and
+    /// processing the same samples produces bit-identical output, which allows rewinding
+    /// and rollback netcode.
+    pub fn snapshot(&self) -> ChipSnapshot {
+        ChipSnapshot {
+            sample_rate: self.sample_rate,
+            channels: self.channels.clone(),
+            groups: self.groups.clone(),
+            scheduled: self.scheduled.clone(),
+            dither: self.dither.clone(),
+            mixer: self.mixer.clone(),
+            sample_head: self.sample_head,
+            last_sample_time: self.last_sample_time,
+        }
+    }
+
+    /// Returns the chip to a previously captured state. Channels and groups are replaced by
+    /// the snapshot's, reusing existing allocations when possible.
+    pub fn restore(&mut self, snapshot: &ChipSnapshot) {
+        self.sample_rate = snapshot.sample_rate;
+        self.channels.clone_from(&snapshot.channels);
+        self.groups.clone_from(&snapshot.groups);
+        self.scheduled.clone_from(&snapshot.scheduled);
+        self.dither.clone_from(&snapshot.dither);
+        self.mixer.clone_from(&snapshot.mixer);
+        self.sample_head = snapshot.sample_head;
+        self.last_sample_time = snapshot.last_sample_time;
+    }
+
+    /// Stops all channels, resets all timers and clears scheduled events.
     pub fn reset(&mut self) {
+        self.scheduled.clear();
         self.sample_head = 0;
         self.last_sample_time = 0.0;
         for channel in &mut self.channels {
@@ -172,6 +469,35 @@ impl SoundChip {
     }
 }
 
+/// The complete state of a [SoundChip] at a given sample. Use [SoundChip::snapshot()] to
+/// obtain this, and [SoundChip::restore()] to go back to it.
+#[derive(Debug, Clone)]
+pub struct ChipSnapshot {
+    sample_rate: u32,
+    channels: Vec<Channel>,
+    groups: Vec<ChipGroup>,
+    scheduled: Vec<ScheduledEvent>,
+    dither: DitherState,
+    mixer: Mixer,
+    sample_head: usize,
+    last_sample_time: f64,
+}
+
+// An event waiting in the SoundChip's schedule.
+#[derive(Debug, Clone)]
+struct ScheduledEvent {
+    sample: usize,
+    channel: usize,
+    event: ChannelEvent,
+}
+
+impl ChipSnapshot {
+    /// The chip time when this snapshot was taken, in seconds.
+    pub fn time(&self) -> f64 {
+        self.sample_head as f64 / self.sample_rate as f64
+    }
+}
+
 /// Iterates a specified number of samples. Use [SoundChip::iter()] to obtain this.
 pub struct SoundChipIter<'a> {
     chip: &'a mut SoundChip,
@@ -190,3 +516,100 @@ impl<'a> Iterator for SoundChipIter<'a> {
         None
     }
 }
+
+#[test]
+fn snapshot_restore_is_deterministic() {
+    let mut chip = SoundChip::new_msx(44100);
+    chip.channel_init_all(true);
+    chip.channels[0].set_noise(true);
+    chip.channels[1].play_sound(&Sound {
+        volume_env: Some(Envelope::from(KNOTS_VOL_DOWN)),
+        vibratto: Some(VIBRATTO_SUBTLE),
+        ..Default::default()
+    }, false);
+    chip.channels[2].set_hardware_envelope(true);
+    let _ = chip.iter(1000).count();
+
+    let snapshot = chip.snapshot();
+    let first: Vec<(i16, i16)> = chip.iter(4000).map(|s| (s.left, s.right)).collect();
+    chip.restore(&snapshot);
+    assert_eq!(chip.time(), snapshot.time());
+    let second: Vec<(i16, i16)> = chip.iter(4000).map(|s| (s.left, s.right)).collect();
+    assert_eq!(first, second);
+}
+
+#[test]
+fn commands_are_sample_accurate() {
+    let mut chip = SoundChip::new_msx(44100);
+    let (mut sender, receiver) = command_queue(16, chip.sample_rate);
+    chip.set_commands(Some(receiver));
+    let channel = |op| ChipOp::Channel { index: 0, op };
+    sender.send_at(100, channel(ChannelOp::Play)).unwrap();
+    sender.send_at(200, channel(ChannelOp::Stop)).unwrap();
+    let mut active = Vec::new();
+    for _ in 0..300 {
+        chip.process_sample();
+        active.push(chip.channels[0].is_playing());
+    }
+    assert!(!active[99] && active[100] && active[199] && !active[200]);
+    assert_eq!(sender.now(), 299);
+}
+
+#[test]
+fn scheduled_events_ignore_block_size() {
+    let render = |block: usize| {
+        let mut chip = SoundChip::new_msx(44100);
+        chip.schedule(0.01, 1, ChannelEvent::PlaySound { sound: Sound::default(), release: false });
+        chip.schedule(0.02, 1, ChannelOp::MidiNote(72.0));
+        chip.schedule(0.02, 1, ChannelOp::Volume(0.5));
+        chip.schedule(0.03, 1, ChannelOp::Release);
+        chip.schedule(0.04, 1, ChannelOp::Stop);
+        let mut result = Vec::new();
+        while result.len() < 2205 {
+            result.extend(chip.iter(block).map(|s| (s.left, s.right)));
+        }
+        assert_eq!(chip.scheduled_len(), 0);
+        result.truncate(2205);
+        result
+    };
+    let reference = render(2205);
+    assert!(reference[..441].iter().all(|s| *s == (0, 0)));
+    assert!(reference[441..1764].iter().any(|s| *s != (0, 0)));
+    assert_eq!(render(64), reference);
+    assert_eq!(render(1), reference);
+}
+
+#[test]
+fn render_into_matches_process_sample() {
+    let new_chip = || {
+        let mut chip = SoundChip::new_msx_scc(44100);
+        chip.channel_init_all(true);
+        chip.channels[4].set_midi_note(67.0);
+        chip.schedule(0.005, 4, ChannelOp::Stop);
+        // Envelopes are processed between spans and within them, at the same samples.
+        chip.channels[5].play_sound(&Sound {
+            volume_env: Some(Envelope::from(KNOTS_VOL_DOWN).scale_time(0.05)),
+            vibratto: Some(VIBRATTO_SUBTLE),
+            ..Default::default()
+        }, false);
+        chip
+    };
+    let mut chip = new_chip();
+    let expected: Vec<i16> = chip.iter(3000).flat_map(|s| [s.left, s.right]).collect();
+
+    let mut chip = new_chip();
+    let mut interleaved = [0; 6000];
+    chip.render_into_i16(&mut interleaved);
+    assert_eq!(interleaved.as_slice(), expected.as_slice());
+
+    let mut chip = new_chip();
+    let mut left = [0; 3000];
+    let mut right = [0; 3000];
+    chip.render_into_planar_i16(&mut left, &mut right);
+    assert!((0..3000).all(|i| left[i] == expected[i * 2] && right[i] == expected[i * 2 + 1]));
+
+    let mut chip = new_chip();
+    let mut mono = [0u8; 1000];
+    chip.render_into_format(&mut mono, OutputOptions { layout: OutputLayout::Mono, dither: Dither::TpdfShaped });
+    assert!(mono.iter().any(|s| *s != 128));
+}
@@ -1,7 +1,9 @@
 mod pan;
 mod chip;
+mod mixer;
 mod pitch;
 mod noise;
+mod output;
 mod volume;
 mod tremolo;
 mod vibratto;
@@ -9,8 +11,10 @@ mod wavetable;
 
 pub use pan::*;
 pub use chip::*;
+pub use mixer::*;
 pub use pitch::*;
 pub use noise::*;
+pub use output::*;
 pub use volume::*;
 pub use tremolo::*;
 pub use vibratto::*;
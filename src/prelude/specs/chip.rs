@@ -1,8 +1,9 @@
-use super::{SpecsPan, SpecsWavetable, SpecsVolume, SpecsNoise, SpecsPitch};
+use super::{SpecsPan, SpecsWavetable, SpecsVolume, SpecsNoise, SpecsPitch, NoiseMix, SpecsOutput};
 
 /// Sound chip's audio properties, which can be manipulated to mimic various
 /// kinds of audio hardware per channel.
 #[derive(Debug, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct SpecsChip {
     /// With the default value of Some(60.0) in Hertz, envelopes aren't processed on every sample.
     /// The last envelope value will be re-used during each period, which is very accurate to how
@@ -14,4 +15,8 @@ pub struct SpecsChip {
     pub pitch: SpecsPitch,
     pub volume: SpecsVolume,
     pub noise: SpecsNoise,
+    /// How noise is combined with the wavetable when the noise envelope is above zero.
+    pub noise_mix: NoiseMix,
+    /// Analog filtering applied to the chip's output, before the master mixer.
+    pub output: SpecsOutput,
 }
@@ -0,0 +1,46 @@
+/// The processing specs for the SoundChip's master mixer, applied after all channels are
+/// summed. Use [SoundChip::set_mixer()](crate::prelude::SoundChip::set_mixer) to change it.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SpecsMixer {
+    /// How the summed channels are kept within the -1.0 to 1.0 range.
+    pub mode: MixMode,
+    /// Divides the mix by the square root of the number of channels, so chips with many
+    /// channels don't clip as easily.
+    pub auto_gain: bool,
+    /// Time in seconds for master volume changes to ramp to the new value, avoiding clicks.
+    pub volume_ramp: f32,
+}
+
+impl Default for SpecsMixer {
+    fn default() -> Self {
+        Self {
+            mode: MixMode::Sine { compression: 1.6 },
+            auto_gain: false,
+            volume_ramp: 0.01,
+        }
+    }
+}
+
+/// The mixer's response to loud signals. All modes clip anything left above 1.0.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum MixMode {
+    /// No coloring, the mix is divided by the headroom. A headroom of 2.0 allows two
+    /// channels at full volume before clipping.
+    Linear { headroom: f32 },
+    /// Sine shaped soft clip. Higher compression values are quieter but saturate less.
+    Sine { compression: f32 },
+    /// Hyperbolic tangent soft clip. Higher drive values are louder and saturate more.
+    Tanh { drive: f32 },
+    /// Cubic soft clip, which is transparent for quiet signals and reaches 1.0 at full scale.
+    Cubic,
+    /// Transparent below the threshold. Louder peaks are detected ahead of time (delaying
+    /// the output by "lookahead" seconds) and smoothly attenuated, recovering over
+    /// "release" seconds.
+    Limiter {
+        threshold: f32,
+        lookahead: f32,
+        release: f32,
+    },
+}
@@ -4,6 +4,7 @@ use crate::prelude::*;
 /// The frequency multiplier in the "pitch" struct is applied *after* quantizing the pitch,
 /// allowing you to map a typical MIDI range like C3 to C5 to a much higher frequency
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum SpecsNoise {
     /// No Noise
     None,
@@ -20,8 +21,9 @@ pub enum SpecsNoise {
         volume_steps: u8,
         pitch: SpecsPitch,
     },
-    /// Not implemented yet.
     /// Wavetable samples are mixed with noise resulting in a different wave on each cycle.
+    /// "mix" is the noise amount from 0.0 (clean wave) to 1.0 (pure noise), and is scaled
+    /// by the noise envelope. Noise pitch follows the tone, since it's generated per sample.
     WaveTable { mix: f32 },
 }
 
@@ -38,3 +40,44 @@ impl Default for SpecsNoise {
         }
     }
 }
+
+/// How the noise output is combined with the wavetable output when both are active.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum NoiseMix {
+    /// Noise replaces the wave whenever the noise envelope is above zero.
+    #[default]
+    Overwrite,
+    /// Output is high only when both wave and noise are high, like the AY-3-8910 mixer.
+    And,
+    /// Wave and noise are summed (and halved to stay in range).
+    Add,
+    /// Blends from wave to noise, using the noise envelope value as the amount.
+    Crossfade,
+    /// Wave is multiplied by the noise (ring modulation).
+    Ring,
+}
+
+impl NoiseMix {
+    /// Combines a wave value and a noise value, both in the -1.0 to 1.0 range.
+    /// "amount" is the current noise envelope value, from 0.0 to 1.0.
+    pub fn mix(self, wave: f32, noise: f32, amount: f32) -> f32 {
+        match self {
+            NoiseMix::Overwrite => noise,
+            NoiseMix::And => wave.min(noise),
+            NoiseMix::Add => (wave + noise) * 0.5,
+            NoiseMix::Crossfade => wave + ((noise - wave) * amount.clamp(0.0, 1.0)),
+            NoiseMix::Ring => wave * noise,
+        }
+    }
+}
+
+#[test]
+fn noise_mix_modes() {
+    assert_eq!(NoiseMix::Overwrite.mix(1.0, -1.0, 1.0), -1.0);
+    assert_eq!(NoiseMix::And.mix(1.0, -1.0, 1.0), -1.0);
+    assert_eq!(NoiseMix::And.mix(1.0, 1.0, 1.0), 1.0);
+    assert_eq!(NoiseMix::Add.mix(1.0, 0.0, 1.0), 0.5);
+    assert_eq!(NoiseMix::Crossfade.mix(1.0, -1.0, 0.25), 0.5);
+    assert_eq!(NoiseMix::Ring.mix(0.5, -1.0, 1.0), -0.5);
+}
@@ -0,0 +1,42 @@
+use alloc::borrow::Cow;
+
+/// The analog stage between a chip's DAC and the speaker. Output capacitors and RC networks
+/// remove DC offsets and soften high frequencies, which is part of what makes each chip sound
+/// the way it does. Channels in a [ChipGroup](crate::prelude::ChipGroup) share the group's
+/// output stage, other channels filter their own output.
+#[derive(Debug, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SpecsOutput {
+    /// Applied in order. No filters leaves the output untouched.
+    pub filters: Cow<'static, [SpecsFilter]>,
+}
+
+/// A single output filter.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SpecsFilter {
+    pub kind: FilterKind,
+    /// Cutoff frequency in Hz. Clamped to just under half the sample rate.
+    pub cutoff: f32,
+    pub poles: FilterPoles,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum FilterKind {
+    /// Removes frequencies below the cutoff, like a coupling capacitor. Also removes any
+    /// DC offset, i.e. from volume specs with "clip_negative_values".
+    HighPass,
+    /// Removes frequencies above the cutoff.
+    LowPass,
+}
+
+/// The filter's steepness.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum FilterPoles {
+    /// 6 dB per octave, like a single RC stage.
+    One,
+    /// 12 dB per octave, with a Butterworth response.
+    Two,
+}
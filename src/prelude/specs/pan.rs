@@ -1,5 +1,6 @@
 /// The processing specs for pan values.
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct SpecsPan {
     /// Quantizes the stereo pan state, i.e. 4 bit pan register = 16 steps.
     pub steps: Option<u16>,
@@ -2,6 +2,7 @@ use core::ops::RangeInclusive;
 
 /// The processing specs for pitch values. Usually Tone and Noise will have different pitch specs.
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct SpecsPitch {
     /// Fixed multiplier.
     pub multiplier: f32,
@@ -10,6 +11,9 @@ pub struct SpecsPitch {
     /// Optional quantization steps within the provided range.
     /// Has no effect without a valid range.
     pub steps: Option<u16>,
+    /// Optional hardware-like frequency divider. When used, range and steps are ignored
+    /// and frequencies snap to the ones the divider can produce.
+    pub divider: Option<SpecsDivider>,
 }
 
 impl Default for SpecsPitch {
@@ -18,6 +22,79 @@ impl Default for SpecsPitch {
             multiplier: 1.0,
             range: Some(16.35 ..= 16744.04),
             steps: Some(4096),
+            divider: None,
         }
     }
 }
+
+/// Pitch quantization based on a period register, like most sound chips:
+/// "frequency = clock / (divisor * period)". Since the period is an integer, high notes
+/// get progressively out of tune, exactly like the real hardware.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SpecsDivider {
+    /// The chip's master clock in Hz.
+    pub clock: f32,
+    /// Fixed clock divisor, i.e. 16 for the AY-3-8910 tone generators.
+    pub divisor: f32,
+    /// The period register's width in bits, i.e. 12 for the AY-3-8910 tone generators.
+    pub bits: u8,
+    /// True if the hardware counts "period + 1" (like the NES APU), false if it counts
+    /// "period" and treats zero as one (like the AY-3-8910).
+    pub plus_one: bool,
+}
+
+impl SpecsDivider {
+    /// The period register value closest to a frequency.
+    pub fn period(&self, frequency: f32) -> u32 {
+        let max = ((1u64 << self.bits.min(32)) - 1) as f32;
+        let min = if self.plus_one { 0.0 } else { 1.0 };
+        let period = (self.clock / (self.divisor * frequency)) - self.offset();
+        libm::roundf(period).clamp(min, max) as u32
+    }
+
+    /// The frequency produced by a period register value.
+    pub fn frequency(&self, period: u32) -> f32 {
+        let period = if self.plus_one { period } else { period.max(1) };
+        self.clock / (self.divisor * (period as f32 + self.offset()))
+    }
+
+    /// Snaps a frequency to the closest one the hardware can produce.
+    pub fn quantize(&self, frequency: f32) -> f32 {
+        self.frequency(self.period(frequency))
+    }
+
+    fn offset(&self) -> f32 {
+        if self.plus_one {
+            1.0
+        } else {
+            0.0
+        }
+    }
+}
+
+#[test]
+fn divider_quantization() {
+    let ay = SpecsDivider {
+        clock: 1_789_772.5,
+        divisor: 16.0,
+        bits: 12,
+        plus_one: false,
+    };
+    // A4 is register 254, slightly sharp
+    assert_eq!(ay.period(440.0), 254);
+    assert!((ay.quantize(440.0) - 440.39).abs() < 0.01);
+    // High notes are badly out of tune, and the register can't go below 1
+    assert_eq!(ay.period(50_000.0), 2);
+    assert_eq!(ay.period(1_000_000.0), 1);
+    // Low notes are clamped by the register width
+    assert_eq!(ay.period(1.0), 4095);
+
+    let nes = SpecsDivider {
+        plus_one: true,
+        bits: 11,
+        ..ay
+    };
+    assert_eq!(nes.period(440.0), 253);
+    assert_eq!(nes.period(1_000_000.0), 0);
+}
@@ -1,5 +1,9 @@
+use super::SpecsDivider;
+use alloc::borrow::Cow;
+
 /// The processing specs for volume values.
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct SpecsVolume {
     /// The maximum number of volume states the chip can render, i.e. 4 bit volume register = 16 steps.
     pub steps: Option<u16>,
@@ -14,6 +18,44 @@ pub struct SpecsVolume {
     pub gain: f32,
     /// Certain chips (like the AY-3-8910) appear to only output positive values.
     pub clip_negative_values: bool,
+    /// Optional hardware envelope generator, shared by all channels in the same
+    /// [ChipGroup](crate::prelude::ChipGroup). Channels must opt in with
+    /// [Channel::set_hardware_envelope()](crate::prelude::Channel::set_hardware_envelope).
+    pub hardware_envelope: Option<SpecsHardwareEnvelope>,
+    /// Optional measured output level for each volume step, from silence to full volume.
+    /// Replaces "steps" and "exponent": the volume is quantized to the table's length.
+    pub table: Option<Cow<'static, [f32]>>,
+    /// Optional non-linear lookup of the combined output of a [ChipGroup](crate::prelude::ChipGroup),
+    /// for chips that mix their channels through a shared DAC, like the NES. Only used if the
+    /// channel is in a group created from specs with a mix table, otherwise the channel falls
+    /// back to its own volume curve.
+    pub mix_table: Option<SpecsMixTable>,
+}
+
+/// A lookup table for the combined output of a group of channels, like the NES APU's
+/// "pulse_table" and "tnd_table". While in the group, channels output their volume linearly,
+/// scaled by their weight, and the group's sum is mapped through the table.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SpecsMixTable {
+    /// The combined output for each table position, from silence up. Positions between
+    /// entries are interpolated, positions past the end use the last entry.
+    pub levels: Cow<'static, [f32]>,
+    /// The table position reached by this channel alone at full volume, i.e. 15.0 for the
+    /// NES pulse channels, and 45.0 for the triangle since the "tnd_table" weighs it by 3.
+    pub weight: f32,
+}
+
+/// The processing specs for an AY-3-8910 style hardware envelope generator, which
+/// runs at audio rate and overrides the channel volume when enabled.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SpecsHardwareEnvelope {
+    /// Volume steps per ramp, i.e. 16 for the AY-3-8910 and 32 for the YM2149.
+    pub steps: u8,
+    /// Optional divider used to quantize the envelope frequency (ramps per second) and
+    /// to convert envelope period registers into frequencies.
+    pub divider: Option<SpecsDivider>,
 }
 
 impl Default for SpecsVolume {
@@ -24,6 +66,9 @@ impl Default for SpecsVolume {
             exponent: 2.5,
             gain: 1.0,
             clip_negative_values: false,
+            hardware_envelope: None,
+            table: None,
+            mix_table: None,
         }
     }
 }
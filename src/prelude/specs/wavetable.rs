@@ -1,29 +1,50 @@
 use crate::prelude::{Knot, NormalSigned};
+use alloc::borrow::Cow;
 use crate::presets::*;
 
 /// Controls the wavetable length, quantization and looping. SoundChip always uses a wavetable, even
 /// for square waves.
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct SpecsWavetable {
     /// Optional default waveform, applied to the wavetable when creating a
-    /// new channel using Channel::from(specs).
-    pub default_waveform: Option<&'static [Knot<NormalSigned>]>,
+    /// new channel using Channel::from(specs). Presets borrow static knots,
+    /// while deserialized specs own theirs.
+    pub default_waveform: Option<Cow<'static, [Knot<NormalSigned>]>>,
     /// The length of the wavetable (how many samples per cycle)
     pub sample_count: usize,
-    /// TODO: This will need a LoopKind enum.
+    /// If false, the wave restarts from the beginning on every pitch change instead of
+    /// preserving its phase. Sampled sounds loop according to [crate::prelude::PcmLoop] instead.
     pub use_loop: bool,
     /// The number of steps per sample. For a PSG with only square waves this number doesn't matter
     /// as long as it's 1 or higher. For a simple wavetable like the SCC this number is 256 (1 byte).
     pub steps: Option<u16>,
+    /// Removes the aliasing caused by hard edges in the wave, mostly audible on high notes.
+    pub anti_aliasing: AntiAliasing,
+}
+
+/// How hard edges between wavetable samples are rendered.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum AntiAliasing {
+    /// Edges snap to the nearest output sample, which folds high harmonics back as
+    /// inharmonic tones.
+    #[default]
+    None,
+    /// Each edge is smoothed over the two output samples around it according to its exact
+    /// position, removing most of the fold-back while keeping the wave's hard edges.
+    /// Delays the channel's output by one sample.
+    PolyBlep,
 }
 
 impl Default for SpecsWavetable {
     fn default() -> Self {
         Self {
-            default_waveform: Some(KNOTS_WAVE_TRIANGLE),
+            default_waveform: Some(Cow::Borrowed(KNOTS_WAVE_TRIANGLE)),
             steps: Some(32),
             sample_count: 32,
             use_loop: true,
+            anti_aliasing: AntiAliasing::None,
         }
     }
 }
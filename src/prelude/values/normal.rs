@@ -17,6 +17,16 @@ impl Normal {
     pub const HALF: Self = Self(u16::MAX / 2);
     pub const THREE_QUARTER: Self = Self((u16::MAX / 4) * 3);
     pub const ONE: Self = Self(u16::MAX);
+
+    /// The internal value, without conversion.
+    pub fn raw(self) -> u16 {
+        self.0
+    }
+
+    /// Creates a value from its internal representation, without conversion.
+    pub const fn from_raw(raw: u16) -> Self {
+        Self(raw)
+    }
 }
 
 /// Returns an f32 value between -1.0 and 1.0 (inclusive).
@@ -56,6 +66,24 @@ impl PartialEq for Normal {
 
 impl KnotValue for Normal {}
 
+/// Serialized as a float for readability. Uses f64 and rounding so that
+/// saving and loading never changes the internal value.
+#[cfg(feature = "serde")]
+impl serde::Serialize for Normal {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_f64(self.0 as f64 / MAX as f64)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Normal {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = <f64 as serde::Deserialize>::deserialize(deserializer)?;
+        let value = libm::round(value * MAX as f64).clamp(u16::MIN as f64, u16::MAX as f64);
+        Ok(Self(value as u16))
+    }
+}
+
 #[test]
 fn normal_value_clip() {
     let a = -0.5;
@@ -18,6 +18,16 @@ impl NormalSigned {
     pub const NEG_HALF: Self = Self(i16::MIN / 2);
     pub const NEG_THREE_QUARTER: Self = Self((i16::MIN / 4) * 3);
     pub const NEG_ONE: Self = Self(i16::MIN);
+
+    /// The internal value, without conversion.
+    pub fn raw(self) -> i16 {
+        self.0
+    }
+
+    /// Creates a value from its internal representation, without conversion.
+    pub const fn from_raw(raw: i16) -> Self {
+        Self(raw)
+    }
 }
 
 /// Returns an f32 value between -1.0 and 1.0 (inclusive).
@@ -57,6 +67,24 @@ impl PartialEq for NormalSigned {
 
 impl KnotValue for NormalSigned {}
 
+/// Serialized as a float for readability. Uses f64 and rounding so that
+/// saving and loading never changes the internal value.
+#[cfg(feature = "serde")]
+impl serde::Serialize for NormalSigned {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_f64(self.0 as f64 / MAX as f64)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for NormalSigned {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = <f64 as serde::Deserialize>::deserialize(deserializer)?;
+        let value = libm::round(value * MAX as f64).clamp(i16::MIN as f64, i16::MAX as f64);
+        Ok(Self(value as i16))
+    }
+}
+
 
 #[test]
 fn normal_signed_value_clip() {
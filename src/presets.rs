@@ -4,6 +4,7 @@ mod chips;
 // mod envelopes;
 mod knots;
 mod noise;
+mod output;
 mod pan;
 mod pitch;
 mod tremolo;
@@ -15,6 +16,7 @@ pub use chips::*;
 // pub use envelopes::*;
 pub use knots::*;
 pub use noise::*;
+pub use output::*;
 pub use pan::*;
 pub use pitch::*;
 pub use tremolo::*;
@@ -10,6 +10,8 @@ pub const SPEC_CHIP_CLEAN:SpecsChip = SpecsChip {
     pitch: SPEC_PITCH_CLEAN,
     volume: SPEC_VOLUME_CLEAN,
     noise: SPEC_NOISE_POKEY,
+    noise_mix: NoiseMix::Overwrite,
+    output: SPEC_OUTPUT_CLEAN,
 };
 
 /// Square wave, no noise;
@@ -17,9 +19,11 @@ pub const SPEC_CHIP_PSG:SpecsChip = SpecsChip {
     envelope_rate: Some(60.0),
     wavetable: SPEC_WAVE_PSG,
     pan: SPEC_PAN_MONO,
-    pitch: SPEC_PITCH_PSG,
-    volume: SPEC_VOLUME_PSG,
+    pitch: SPEC_PITCH_AY,
+    volume: SPEC_VOLUME_AY,
     noise: SpecsNoise::None,
+    noise_mix: NoiseMix::Overwrite,
+    output: SPEC_OUTPUT_AY,
 };
 
 /// Square wave, capable of white noise;
@@ -27,9 +31,11 @@ pub const SPEC_CHIP_PSG_NOISE:SpecsChip = SpecsChip {
     envelope_rate: Some(60.0),
     wavetable: SPEC_WAVE_PSG,
     pan: SPEC_PAN_MONO,
-    pitch: SPEC_PITCH_PSG,
-    volume: SPEC_VOLUME_PSG,
+    pitch: SPEC_PITCH_AY,
+    volume: SPEC_VOLUME_AY,
     noise: SPEC_NOISE_MSX,
+    noise_mix: NoiseMix::Overwrite,
+    output: SPEC_OUTPUT_AY,
 };
 
 /// 32 byte wavetable, 1 byte per sample (32x256).
@@ -37,9 +43,11 @@ pub const SPEC_CHIP_SCC:SpecsChip = SpecsChip {
     envelope_rate: Some(60.0),
     wavetable: SPEC_WAVE_SCC,
     pan: SPEC_PAN_STEREO,
-    pitch: SPEC_PITCH_PSG,
+    pitch: SPEC_PITCH_SCC,
     volume: SPEC_VOLUME_PSG,
     noise: SpecsNoise::None,
+    noise_mix: NoiseMix::Overwrite,
+    output: SPEC_OUTPUT_DC_BLOCK,
 };
 
 /// 32 samples, 5 bits each (32x32).
@@ -47,9 +55,11 @@ pub const SPEC_CHIP_PCE:SpecsChip = SpecsChip {
     envelope_rate: Some(60.0),
     wavetable: SPEC_WAVE_PCE,
     pan: SPEC_PAN_STEREO,
-    pitch: SPEC_PITCH_PSG,
+    pitch: SPEC_PITCH_PCE,
     volume: SPEC_VOLUME_PCE,
     noise: SPEC_NOISE_PCE,
+    noise_mix: NoiseMix::Overwrite,
+    output: SPEC_OUTPUT_DC_BLOCK,
 };
 
 /// NES APU Square wave. Adjust the wavetable for duty dycle.;
@@ -57,9 +67,11 @@ pub const SPEC_CHIP_NES_SQUARE:SpecsChip = SpecsChip {
     envelope_rate: Some(60.0),
     wavetable: SPEC_WAVE_NES_SQUARE,
     pan: SPEC_PAN_MONO,
-    pitch: SPEC_PITCH_PSG,
+    pitch: SPEC_PITCH_NES,
     volume: SPEC_VOLUME_NES,
     noise: SpecsNoise::None,
+    noise_mix: NoiseMix::Overwrite,
+    output: SPEC_OUTPUT_NES,
 };
 
 /// 32 x 16 Triangle wave (as long as the envelope is KNOTS_WAVE_TRIANGLE);
@@ -67,9 +79,11 @@ pub const SPEC_CHIP_NES_TRIANGLE:SpecsChip = SpecsChip {
     envelope_rate: Some(60.0),
     wavetable: SPEC_WAVE_NES_TRIANGLE,
     pan: SPEC_PAN_MONO,
-    pitch: SPEC_PITCH_PSG,
+    pitch: SPEC_PITCH_NES_TRIANGLE,
     volume: SPEC_VOLUME_NES_TRIANGLE,
     noise: SpecsNoise::None,
+    noise_mix: NoiseMix::Overwrite,
+    output: SPEC_OUTPUT_NES,
 };
 
 /// NES APU Noise.
@@ -78,8 +92,10 @@ pub const SPEC_CHIP_NES_NOISE:SpecsChip = SpecsChip {
     wavetable: SPEC_WAVE_FLAT,
     pan: SPEC_PAN_MONO,
     pitch: SPEC_PITCH_PSG,
-    volume: SPEC_VOLUME_NES,
-    noise: SPEC_NOISE_NES
+    volume: SPEC_VOLUME_NES_NOISE,
+    noise: SPEC_NOISE_NES,
+    noise_mix: NoiseMix::Overwrite,
+    output: SPEC_OUTPUT_NES,
 };
 
 /// NES APU Noise with setting #2.
@@ -88,16 +104,20 @@ pub const SPEC_CHIP_NES_NOISE_MELODIC:SpecsChip = SpecsChip {
     wavetable: SPEC_WAVE_FLAT,
     pan: SPEC_PAN_MONO,
     pitch: SPEC_PITCH_PSG,
-    volume: SPEC_VOLUME_NES,
-    noise: SPEC_NOISE_NES_MELODIC
+    volume: SPEC_VOLUME_NES_NOISE,
+    noise: SPEC_NOISE_NES_MELODIC,
+    noise_mix: NoiseMix::Overwrite,
+    output: SPEC_OUTPUT_NES,
 };
 
-/// NES Wave channel.
+/// NES DMC channel. Use [crate::prelude::Channel::set_dpcm()] to play delta streams.
 pub const SPEC_CHIP_NES_DMC:SpecsChip = SpecsChip {
     envelope_rate: Some(60.0),
     wavetable: SPEC_WAVE_NES_DMC,
     pan: SPEC_PAN_MONO,
     pitch: SPEC_PITCH_PSG,
-    volume: SPEC_VOLUME_PSG,
-    noise: SPEC_NOISE_NES
+    volume: SPEC_VOLUME_NES_DMC,
+    noise: SPEC_NOISE_NES,
+    noise_mix: NoiseMix::Overwrite,
+    output: SPEC_OUTPUT_NES,
 };
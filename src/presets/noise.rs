@@ -1,4 +1,5 @@
 use crate::prelude::*;
+use crate::presets::*;
 
 // Noise
 const FREQ_C0:f32 = 16.35;
@@ -12,12 +13,19 @@ const FREQ_C9:f32 = 8372.0;
 const FREQ_C10:f32 = 16744.04;
 const FREQ_GS5:f32 = 830.61;
 
+/// The AY-3-8910 noise period is a 5 bit register, where frequency = clock / (16 * period).
 pub const SPEC_NOISE_MSX: SpecsNoise = SpecsNoise::Random {
     volume_steps: 2,
     pitch: SpecsPitch {
         multiplier: 55.0,
         steps: Some(32),
         range: Some(FREQ_C3 ..= FREQ_GS5),
+        divider: Some(SpecsDivider {
+            clock: CLOCK_MSX_PSG,
+            divisor: 16.0,
+            bits: 5,
+            plus_one: false,
+        }),
     },
 };
 
@@ -27,6 +35,7 @@ pub const SPEC_NOISE_PCE: SpecsNoise = SpecsNoise::Random {
         multiplier: 55.0,
         steps: Some(4096),
         range: Some(FREQ_C0 ..= FREQ_C10),
+        divider: None,
     },
 };
 
@@ -38,6 +47,7 @@ pub const SPEC_NOISE_POKEY: SpecsNoise = SpecsNoise::Melodic {
         multiplier: 5.0,
         steps: Some(128),
         range: Some(FREQ_C1 ..= FREQ_C9),
+        divider: None,
     },
 };
 
@@ -49,6 +59,7 @@ pub const SPEC_NOISE_NES: SpecsNoise = SpecsNoise::Random {
         multiplier: 15.46,
         steps: Some(32),
         range: Some(FREQ_C2 ..= FREQ_C8),
+        divider: None,
     },
 };
 
@@ -61,5 +72,6 @@ pub const SPEC_NOISE_NES_MELODIC: SpecsNoise = SpecsNoise::Melodic {
         multiplier: 5.0,
         steps: Some(32),
         range: Some(FREQ_C2 ..= FREQ_C8),
+        divider: None,
     },
 };
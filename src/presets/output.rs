@@ -0,0 +1,39 @@
+use crate::prelude::*;
+use alloc::borrow::Cow;
+
+/// No filtering at all.
+pub const SPEC_OUTPUT_CLEAN:SpecsOutput = SpecsOutput {
+    filters: Cow::Borrowed(&[]),
+};
+
+/// Coupling capacitor only, removes the DC offset of chips with positive-only output.
+pub const SPEC_OUTPUT_DC_BLOCK:SpecsOutput = SpecsOutput {
+    filters: Cow::Borrowed(&[
+        SpecsFilter { kind: FilterKind::HighPass, cutoff: 20.0, poles: FilterPoles::One },
+    ]),
+};
+
+/// AY-3-8910 as wired in most MSX computers: DC blocking plus a gentle RC low-pass.
+pub const SPEC_OUTPUT_AY:SpecsOutput = SpecsOutput {
+    filters: Cow::Borrowed(&[
+        SpecsFilter { kind: FilterKind::HighPass, cutoff: 20.0, poles: FilterPoles::One },
+        SpecsFilter { kind: FilterKind::LowPass, cutoff: 15_000.0, poles: FilterPoles::One },
+    ]),
+};
+
+/// NES/Famicom APU: two first-order high-pass filters at 90 Hz and 440 Hz, and a
+/// first-order low-pass at 14 kHz.
+pub const SPEC_OUTPUT_NES:SpecsOutput = SpecsOutput {
+    filters: Cow::Borrowed(&[
+        SpecsFilter { kind: FilterKind::HighPass, cutoff: 90.0, poles: FilterPoles::One },
+        SpecsFilter { kind: FilterKind::HighPass, cutoff: 440.0, poles: FilterPoles::One },
+        SpecsFilter { kind: FilterKind::LowPass, cutoff: 14_000.0, poles: FilterPoles::One },
+    ]),
+};
+
+/// Game Boy (DMG) output capacitor, roughly 28 Hz.
+pub const SPEC_OUTPUT_GAMEBOY:SpecsOutput = SpecsOutput {
+    filters: Cow::Borrowed(&[
+        SpecsFilter { kind: FilterKind::HighPass, cutoff: 28.0, poles: FilterPoles::One },
+    ]),
+};
@@ -1,15 +1,94 @@
 use crate::prelude::*;
 
+/// Master clock of the MSX PSG (AY-3-8910), in Hz.
+pub const CLOCK_MSX_PSG: f32 = 1_789_772.5;
+
+/// Master clock of the MSX SCC and the PC-Engine, in Hz.
+pub const CLOCK_MSX_SCC: f32 = 3_579_545.0;
+
+/// CPU clock of the NTSC NES, which drives the APU, in Hz.
+pub const CLOCK_NES: f32 = 1_789_773.0;
+
+/// Clock of the SN76489 in the Sega Master System, in Hz.
+pub const CLOCK_SN76489: f32 = 3_579_545.0;
+
 pub const SPEC_PITCH_CLEAN: SpecsPitch = SpecsPitch {
     multiplier: 1.0,
     range: None,
     steps: None,
+    divider: None,
 };
 
+/// Just an approximation, 4096 pitch steps in 10 octaves.
 pub const SPEC_PITCH_PSG: SpecsPitch = SpecsPitch {
     multiplier: 1.0,
     range: Some(16.35 ..= 16744.04),
     steps: Some(4096),
+    divider: None,
+};
+
+/// AY-3-8910 tone: 12 bit period, frequency = clock / (16 * period).
+pub const SPEC_PITCH_AY: SpecsPitch = SpecsPitch {
+    divider: Some(SpecsDivider {
+        clock: CLOCK_MSX_PSG,
+        divisor: 16.0,
+        bits: 12,
+        plus_one: false,
+    }),
+    ..SPEC_PITCH_PSG
+};
+
+/// SCC wavetable: 12 bit period, frequency = clock / (32 * (period + 1)).
+pub const SPEC_PITCH_SCC: SpecsPitch = SpecsPitch {
+    divider: Some(SpecsDivider {
+        clock: CLOCK_MSX_SCC,
+        divisor: 32.0,
+        bits: 12,
+        plus_one: true,
+    }),
+    ..SPEC_PITCH_PSG
 };
 
-pub const SPEC_PITCH_SCC:SpecsPitch = SPEC_PITCH_PSG;
+/// PC-Engine wavetable: 12 bit period, frequency = clock / (32 * period).
+pub const SPEC_PITCH_PCE: SpecsPitch = SpecsPitch {
+    divider: Some(SpecsDivider {
+        clock: CLOCK_MSX_SCC,
+        divisor: 32.0,
+        bits: 12,
+        plus_one: false,
+    }),
+    ..SPEC_PITCH_PSG
+};
+
+/// NES pulse: 11 bit period, frequency = clock / (16 * (period + 1)).
+pub const SPEC_PITCH_NES: SpecsPitch = SpecsPitch {
+    divider: Some(SpecsDivider {
+        clock: CLOCK_NES,
+        divisor: 16.0,
+        bits: 11,
+        plus_one: true,
+    }),
+    ..SPEC_PITCH_PSG
+};
+
+/// NES triangle: 11 bit period, one octave lower than the pulse channels.
+pub const SPEC_PITCH_NES_TRIANGLE: SpecsPitch = SpecsPitch {
+    divider: Some(SpecsDivider {
+        clock: CLOCK_NES,
+        divisor: 32.0,
+        bits: 11,
+        plus_one: true,
+    }),
+    ..SPEC_PITCH_PSG
+};
+
+/// SN76489 tone: 10 bit period, frequency = clock / (32 * period).
+pub const SPEC_PITCH_SN76489: SpecsPitch = SpecsPitch {
+    divider: Some(SpecsDivider {
+        clock: CLOCK_SN76489,
+        divisor: 32.0,
+        bits: 10,
+        plus_one: false,
+    }),
+    ..SPEC_PITCH_PSG
+};
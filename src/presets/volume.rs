@@ -1,4 +1,17 @@
 use crate::prelude::*;
+use crate::presets::*;
+use alloc::borrow::Cow;
+
+/// AY-3-8910 envelope: 16 steps per ramp, frequency = clock / (256 * period).
+pub const SPEC_HARDWARE_ENVELOPE_AY:SpecsHardwareEnvelope = SpecsHardwareEnvelope {
+    steps: 16,
+    divider: Some(SpecsDivider {
+        clock: CLOCK_MSX_PSG,
+        divisor: 256.0,
+        bits: 16,
+        plus_one: false,
+    }),
+};
 
 pub const SPEC_VOLUME_CLEAN:SpecsVolume = SpecsVolume {
     steps: None,
@@ -6,6 +19,9 @@ pub const SPEC_VOLUME_CLEAN:SpecsVolume = SpecsVolume {
     exponent: 2.5,
     gain: 1.0,
     clip_negative_values: false,
+    hardware_envelope: None,
+    table: None,
+    mix_table: None,
 };
 
 pub const SPEC_VOLUME_PSG:SpecsVolume = SpecsVolume {
@@ -14,6 +30,24 @@ pub const SPEC_VOLUME_PSG:SpecsVolume = SpecsVolume {
     exponent: 3.0,
     gain: 1.0,
     clip_negative_values: true,
+    hardware_envelope: None,
+    table: None,
+    mix_table: None,
+};
+
+/// Same as the PSG volume, plus the AY-3-8910 hardware envelope generator and its measured
+/// DAC levels.
+pub const SPEC_VOLUME_AY:SpecsVolume = SpecsVolume {
+    hardware_envelope: Some(SPEC_HARDWARE_ENVELOPE_AY),
+    table: Some(Cow::Borrowed(TABLE_VOLUME_AY)),
+    ..SPEC_VOLUME_PSG
+};
+
+/// SN76489: 16 levels in 2 dB steps, the lowest one is silent.
+pub const SPEC_VOLUME_SN76489:SpecsVolume = SpecsVolume {
+    clip_negative_values: false,
+    table: Some(Cow::Borrowed(TABLE_VOLUME_SN76489)),
+    ..SPEC_VOLUME_PSG
 };
 
 pub const SPEC_VOLUME_SCC:SpecsVolume = SpecsVolume {
@@ -22,6 +56,9 @@ pub const SPEC_VOLUME_SCC:SpecsVolume = SpecsVolume {
     exponent: 3.0,
     gain: 1.0,
     clip_negative_values: true,
+    hardware_envelope: None,
+    table: None,
+    mix_table: None,
 };
 
 pub const SPEC_VOLUME_PCE:SpecsVolume = SpecsVolume {
@@ -30,20 +67,97 @@ pub const SPEC_VOLUME_PCE:SpecsVolume = SpecsVolume {
     exponent: 3.0,
     gain: 1.0,
     clip_negative_values: false,
+    hardware_envelope: None,
+    table: None,
+    mix_table: None,
 };
 
+/// NES pulse channels, mixed through the "pulse_table" when grouped.
 pub const SPEC_VOLUME_NES:SpecsVolume = SpecsVolume {
     steps: Some(16),
     attenuation: 0.0017,
     exponent: 3.0,
     gain: 1.0,
     clip_negative_values: false,
+    hardware_envelope: None,
+    table: None,
+    mix_table: Some(SPEC_MIX_NES_PULSE),
 };
 
+/// NES triangle channel, mixed through the "tnd_table" with a weight of 3.
 pub const SPEC_VOLUME_NES_TRIANGLE:SpecsVolume = SpecsVolume {
     steps: Some(1),
     attenuation: 0.0017,
     exponent: 3.0,
     gain: 1.0,
     clip_negative_values: false,
+    hardware_envelope: None,
+    table: None,
+    mix_table: Some(SPEC_MIX_NES_TRIANGLE),
+};
+
+/// NES noise channel, mixed through the "tnd_table" with a weight of 2.
+pub const SPEC_VOLUME_NES_NOISE:SpecsVolume = SpecsVolume {
+    mix_table: Some(SPEC_MIX_NES_NOISE),
+    ..SPEC_VOLUME_NES
+};
+
+/// NES DMC channel, mixed through the "tnd_table" with its full 7 bit range. Negative values
+/// are kept, since the DMC's levels are centered on zero.
+pub const SPEC_VOLUME_NES_DMC:SpecsVolume = SpecsVolume {
+    clip_negative_values: false,
+    mix_table: Some(SPEC_MIX_NES_DMC),
+    ..SPEC_VOLUME_PSG
+};
+
+/// AY-3-8910 DAC output for each of the 16 volume levels, normalized.
+pub const TABLE_VOLUME_AY:&[f32] = &[
+    0.0, 0.00999, 0.01445, 0.02106, 0.03070, 0.04555, 0.06450, 0.10736,
+    0.12659, 0.20499, 0.29221, 0.37284, 0.49253, 0.63532, 0.80558, 1.0,
+];
+
+/// SN76489 output for each volume level, 2 dB apart (register values reversed, 15 is silent).
+pub const TABLE_VOLUME_SN76489:&[f32] = &[
+    0.0, 0.03981, 0.05012, 0.06310, 0.07943, 0.1, 0.12589, 0.15849,
+    0.19953, 0.25119, 0.31623, 0.39811, 0.50119, 0.63096, 0.79433, 1.0,
+];
+
+// NES mixer approximations from the NESdev wiki, normalized so that a single pulse channel
+// at full volume outputs 1.0 like any other channel.
+const NES_MIX_SCALE: f32 = 95.52 / (8128.0 / 15.0 + 100.0);
+
+const fn nes_mix_table<const N: usize>(numerator: f32, denominator: f32) -> [f32; N] {
+    let mut result = [0.0; N];
+    let mut i = 1;
+    while i < N {
+        result[i] = numerator / (denominator / i as f32 + 100.0) / NES_MIX_SCALE;
+        i += 1;
+    }
+    result
+}
+
+/// NES "pulse_table": both pulse channels combined, 0 to 30.
+pub const TABLE_MIX_NES_PULSE:&[f32] = &nes_mix_table::<31>(95.52, 8128.0);
+
+/// NES "tnd_table": triangle x 3 + noise x 2 + DMC, 0 to 202.
+pub const TABLE_MIX_NES_TND:&[f32] = &nes_mix_table::<203>(163.67, 24329.0);
+
+pub const SPEC_MIX_NES_PULSE:SpecsMixTable = SpecsMixTable {
+    levels: Cow::Borrowed(TABLE_MIX_NES_PULSE),
+    weight: 15.0,
+};
+
+pub const SPEC_MIX_NES_TRIANGLE:SpecsMixTable = SpecsMixTable {
+    levels: Cow::Borrowed(TABLE_MIX_NES_TND),
+    weight: 45.0,
+};
+
+pub const SPEC_MIX_NES_NOISE:SpecsMixTable = SpecsMixTable {
+    levels: Cow::Borrowed(TABLE_MIX_NES_TND),
+    weight: 30.0,
+};
+
+pub const SPEC_MIX_NES_DMC:SpecsMixTable = SpecsMixTable {
+    levels: Cow::Borrowed(TABLE_MIX_NES_TND),
+    weight: 127.0,
 };
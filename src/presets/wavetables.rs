@@ -1,61 +1,70 @@
 use crate::prelude::*;
 use crate::presets::*;
+use alloc::borrow::Cow;
 
 pub const SPEC_WAVE_FLAT:SpecsWavetable = SpecsWavetable {
-    default_waveform: Some(KNOTS_SIGNED_ZERO),
+    default_waveform: Some(Cow::Borrowed(KNOTS_SIGNED_ZERO)),
     sample_count: 8,
     use_loop: true,
     steps: Some(0),
+    anti_aliasing: AntiAliasing::None,
 };
 
 pub const SPEC_WAVE_CLEAN:SpecsWavetable = SpecsWavetable {
-    default_waveform: Some(KNOTS_WAVE_TRIANGLE),
+    default_waveform: Some(Cow::Borrowed(KNOTS_WAVE_TRIANGLE)),
     sample_count: 256,
     use_loop: true,
     steps: Some(256),
+    anti_aliasing: AntiAliasing::None,
 };
 
 pub const SPEC_WAVE_PSG:SpecsWavetable = SpecsWavetable {
-    default_waveform: Some(KNOTS_WAVE_SQUARE),
+    default_waveform: Some(Cow::Borrowed(KNOTS_WAVE_SQUARE)),
     sample_count: 8,
     use_loop: true,
     steps: Some(2),
+    anti_aliasing: AntiAliasing::None,
 };
 
 pub const SPEC_WAVE_SCC:SpecsWavetable = SpecsWavetable {
-    default_waveform: Some(KNOTS_WAVE_TRIANGLE),
+    default_waveform: Some(Cow::Borrowed(KNOTS_WAVE_TRIANGLE)),
     sample_count: 32,
     use_loop: true,
     steps: Some(256),
+    anti_aliasing: AntiAliasing::None,
 };
 
 pub const SPEC_WAVE_PCE:SpecsWavetable = SpecsWavetable {
-    default_waveform: Some(KNOTS_WAVE_TRIANGLE),
+    default_waveform: Some(Cow::Borrowed(KNOTS_WAVE_TRIANGLE)),
     sample_count: 32,
     use_loop: true,
     steps: Some(32),
+    anti_aliasing: AntiAliasing::None,
 };
 
 pub const SPEC_WAVE_NES_SQUARE:SpecsWavetable = SpecsWavetable {
-    default_waveform: Some(KNOTS_WAVE_SQUARE),
+    default_waveform: Some(Cow::Borrowed(KNOTS_WAVE_SQUARE)),
     sample_count: 8,
     use_loop: true,
     steps: Some(2),
+    anti_aliasing: AntiAliasing::None,
 };
 
 pub const SPEC_WAVE_NES_TRIANGLE:SpecsWavetable = SpecsWavetable {
-    default_waveform: Some(KNOTS_WAVE_TRIANGLE),
+    default_waveform: Some(Cow::Borrowed(KNOTS_WAVE_TRIANGLE)),
     sample_count: 32,
     use_loop: true,
     steps: Some(16),
+    anti_aliasing: AntiAliasing::None,
 };
 
-/// Needs testing! use_loop doesn't do anything yet.
-/// May need a frequency multiplier to "stretch" the sample when playing C4?
+/// 7 bit output level of the DMC. The wavetable is only used when no delta stream is set,
+/// see [crate::prelude::Channel::set_dpcm()].
 pub const SPEC_WAVE_NES_DMC:SpecsWavetable = SpecsWavetable {
-    default_waveform: Some(KNOTS_WAVE_TRIANGLE),
+    default_waveform: Some(Cow::Borrowed(KNOTS_WAVE_TRIANGLE)),
     sample_count: 256,
     use_loop: false,
-    // In reality, DPCM meant any sample had to be +1 step or -1 step, never the same.
-    steps: Some(16),
+    // DPCM moves the level 2 steps up or down on every bit, never staying the same.
+    steps: Some(128),
+    anti_aliasing: AntiAliasing::None,
 };
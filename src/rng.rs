@@ -1,6 +1,6 @@
 //! A simple, "old school" LFSR with configurable bit count.
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Rng {
     state: u32,
     mask: u32,
@@ -28,6 +28,16 @@ impl Rng {
         }
     }
 
+    /// Restarts the sequence from a new state, keeping the bit count.
+    /// The same state always produces the same sequence.
+    pub fn set_state(&mut self, state: u32) {
+        self.state = if (state & self.mask) == 0 {
+            DEFAULT_VALUE & self.mask
+        } else {
+            state & self.mask
+        };
+    }
+
     /// Next random u32 value in the sequence
     pub fn next_u32(&mut self) -> u32 {
         let lsb = self.state & 1; // Store least significant bit
@@ -0,0 +1,236 @@
+//! A simple pattern based music sequencer, similar to a "music tracker".
+//! Songs are made of patterns played in the order defined by an order list. Each pattern
+//! contains rows, and each row contains one cell per channel.
+
+mod pattern;
+pub use pattern::*;
+
+mod song;
+pub use song::*;
+
+use crate::prelude::*;
+
+/// Owns a [SoundChip] and plays a [Song] on it. Rows are triggered with sample accuracy
+/// while rendering, so timing doesn't depend on how often (or how many samples) you request.
+pub struct Sequencer {
+    /// The SoundChip driven by this sequencer. Its channels can still be manipulated directly,
+    /// but changes may be overriden by the next row.
+    pub chip: SoundChip,
+    song: Song,
+    playing: bool,
+    order_head: usize,
+    row_head: usize,
+    tick: u8,
+    tick_samples: f64,
+}
+
+impl Sequencer {
+    /// Creates a stopped sequencer. Call [Sequencer::play()] to start playback.
+    pub fn new(chip: SoundChip, song: Song) -> Self {
+        Self {
+            chip,
+            song,
+            playing: false,
+            order_head: 0,
+            row_head: 0,
+            tick: 0,
+            tick_samples: 0.0,
+        }
+    }
+
+    /// The current song.
+    pub fn song(&self) -> &Song {
+        &self.song
+    }
+
+    /// Replaces the current song, stopping playback and rewinding to the beginning.
+    pub fn set_song(&mut self, song: Song) {
+        self.song = song;
+        self.stop();
+    }
+
+    /// Starts (or resumes) playback from the current position.
+    pub fn play(&mut self) {
+        self.playing = true;
+    }
+
+    /// Stops playback and all channels, and rewinds to the beginning of the song.
+    pub fn stop(&mut self) {
+        self.playing = false;
+        self.chip.channel_stop_all();
+        self.set_position(0, 0);
+    }
+
+    /// Pauses playback. Channels are not stopped, so any sound currently playing will continue.
+    pub fn pause(&mut self) {
+        self.playing = false;
+    }
+
+    /// Current playing state.
+    pub fn is_playing(&self) -> bool {
+        self.playing
+    }
+
+    /// The current position as an order list index and a row index.
+    pub fn position(&self) -> (usize, usize) {
+        (self.order_head, self.row_head)
+    }
+
+    /// Jumps to an order list index and row. The row will be triggered on the next sample.
+    pub fn set_position(&mut self, order: usize, row: usize) {
+        self.order_head = order;
+        self.row_head = row;
+        self.tick = 0;
+        self.tick_samples = 0.0;
+    }
+
+    /// Renders a given number of samples on demand, advancing the song.
+    pub fn iter(&mut self, sample_count: usize) -> SequencerIter<'_> {
+        SequencerIter {
+            sequencer: self,
+            head: 0,
+            sample_count,
+        }
+    }
+
+    /// Process a single sample, advancing the song and the chip's internal timer.
+    pub fn process_sample(&mut self) -> Sample<i16> {
+        if self.playing {
+            if self.tick_samples <= 0.0 {
+                self.process_tick();
+                self.tick_samples += self.chip.sample_rate as f64 / self.song.tick_rate() as f64;
+            }
+            self.tick_samples -= 1.0;
+        }
+        self.chip.process_sample()
+    }
+
+    // Advances to the next row once "speed" ticks have passed, and triggers
+    // the current row on its first tick.
+    fn process_tick(&mut self) {
+        if self.tick >= self.song.speed.max(1) {
+            self.tick = 0;
+            self.advance_row();
+            if !self.playing {
+                return;
+            }
+        }
+        if self.tick == 0 {
+            self.trigger_row();
+        }
+        self.tick += 1;
+    }
+
+    fn trigger_row(&mut self) {
+        let Some(pattern) = self
+            .song
+            .order
+            .get(self.order_head)
+            .and_then(|index| self.song.patterns.get(*index))
+        else {
+            return;
+        };
+        let Some(row) = pattern.row(self.row_head) else {
+            return;
+        };
+        for (index, cell) in row.iter().enumerate() {
+            let Some(channel) = self.chip.channels.get_mut(index) else {
+                break;
+            };
+            if let Some(sound) = cell.instrument.and_then(|i| self.song.instruments.get(i)) {
+                channel.set_sound(sound);
+            }
+            if let Some(volume) = cell.volume {
+                channel.set_volume(volume);
+            }
+            match cell.note {
+                CellNote::None => {}
+                CellNote::Note(note) => {
+                    channel.reset();
+                    channel.set_midi_note(note);
+                    channel.play();
+                }
+                CellNote::Release => channel.release(),
+                CellNote::Stop => channel.stop(),
+            }
+        }
+    }
+
+    fn advance_row(&mut self) {
+        self.row_head += 1;
+        let pattern_len = self
+            .song
+            .order
+            .get(self.order_head)
+            .and_then(|index| self.song.patterns.get(*index))
+            .map_or(0, |pattern| pattern.len());
+        if self.row_head < pattern_len {
+            return;
+        }
+        self.row_head = 0;
+        self.order_head += 1;
+        if self.order_head < self.song.order.len() {
+            return;
+        }
+        match self.song.loop_order {
+            Some(order) if order < self.song.order.len() => self.order_head = order,
+            _ => {
+                self.playing = false;
+                self.order_head = 0;
+            }
+        }
+    }
+}
+
+/// Iterates a specified number of samples. Use [Sequencer::iter()] to obtain this.
+pub struct SequencerIter<'a> {
+    sequencer: &'a mut Sequencer,
+    head: usize,
+    sample_count: usize,
+}
+
+impl Iterator for SequencerIter<'_> {
+    type Item = Sample<i16>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.head < self.sample_count {
+            self.head += 1;
+            return Some(self.sequencer.process_sample());
+        }
+        None
+    }
+}
+
+#[test]
+fn sequencer_timing() {
+    use crate::Vec;
+    // 1000 Hz sample rate, 50 Hz tick rate (tempo 125) and speed 2: one row every 40 samples.
+    let mut pattern = Pattern::new(4, 1);
+    pattern.set(0, 0, Cell::note(60.0));
+    pattern.set(2, 0, Cell::release());
+    let song = Song {
+        patterns: Vec::from([pattern]),
+        order: Vec::from([0, 0]),
+        speed: 2,
+        ..Default::default()
+    };
+    let mut chip = SoundChip::new(1000);
+    chip.add_channel(SpecsChip::default());
+    let mut sequencer = Sequencer::new(chip, song);
+    sequencer.play();
+
+    sequencer.process_sample();
+    assert!(sequencer.chip.channels[0].is_playing());
+    assert_eq!(sequencer.position(), (0, 0));
+
+    for _ in sequencer.iter(39) {}
+    assert_eq!(sequencer.position(), (0, 0));
+    sequencer.process_sample();
+    assert_eq!(sequencer.position(), (0, 1));
+
+    // Two patterns with 4 rows each, no loop.
+    for _ in sequencer.iter(40 * 6) {}
+    assert_eq!(sequencer.position(), (1, 3));
+    for _ in sequencer.iter(40) {}
+    assert!(!sequencer.is_playing());
+}
@@ -0,0 +1,119 @@
+use crate::Vec;
+
+/// What a cell does to its channel's note when its row is triggered.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum CellNote {
+    /// Leaves the channel untouched.
+    #[default]
+    None,
+    /// Resets the channel and plays a MIDI note (C4 = 60). Can be fractional.
+    Note(f32),
+    /// Releases the channel's envelopes.
+    Release,
+    /// Stops the channel immediately.
+    Stop,
+}
+
+/// A single pattern cell, i.e. what happens to one channel in one row.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Cell {
+    pub note: CellNote,
+    /// Optional index into the song's instrument list. Applied before the note.
+    pub instrument: Option<usize>,
+    /// Optional channel volume, applied after the instrument.
+    pub volume: Option<f32>,
+}
+
+impl Cell {
+    /// A cell that plays a MIDI note with the channel's current sound.
+    pub fn note(note: impl Into<f32>) -> Self {
+        Self {
+            note: CellNote::Note(note.into()),
+            ..Default::default()
+        }
+    }
+
+    /// A cell that plays a MIDI note with an instrument from the song's list.
+    pub fn note_with(note: impl Into<f32>, instrument: usize) -> Self {
+        Self {
+            note: CellNote::Note(note.into()),
+            instrument: Some(instrument),
+            ..Default::default()
+        }
+    }
+
+    /// A cell that releases the channel.
+    pub fn release() -> Self {
+        Self {
+            note: CellNote::Release,
+            ..Default::default()
+        }
+    }
+
+    /// A cell that stops the channel.
+    pub fn stop() -> Self {
+        Self {
+            note: CellNote::Stop,
+            ..Default::default()
+        }
+    }
+}
+
+/// A grid of cells with a fixed number of rows and channels.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Pattern {
+    channels: usize,
+    cells: Vec<Cell>,
+}
+
+impl Pattern {
+    /// Creates a pattern filled with empty cells.
+    pub fn new(rows: usize, channels: usize) -> Self {
+        Self {
+            channels,
+            cells: (0..rows * channels).map(|_| Cell::default()).collect(),
+        }
+    }
+
+    /// The number of rows.
+    pub fn len(&self) -> usize {
+        if self.channels == 0 {
+            return 0;
+        }
+        self.cells.len() / self.channels
+    }
+
+    /// True if the pattern has no rows.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The number of channels (cells per row).
+    pub fn channels(&self) -> usize {
+        self.channels
+    }
+
+    /// All cells in a row, one per channel.
+    pub fn row(&self, row: usize) -> Option<&[Cell]> {
+        let start = row * self.channels;
+        self.cells.get(start..start + self.channels)
+    }
+
+    /// A single cell.
+    pub fn get(&self, row: usize, channel: usize) -> Option<&Cell> {
+        if channel >= self.channels {
+            return None;
+        }
+        self.cells.get((row * self.channels) + channel)
+    }
+
+    /// Replaces a single cell. Does nothing if row or channel are out of range.
+    pub fn set(&mut self, row: usize, channel: usize, cell: Cell) {
+        if channel >= self.channels {
+            return;
+        }
+        if let Some(current) = self.cells.get_mut((row * self.channels) + channel) {
+            *current = cell;
+        }
+    }
+}
@@ -0,0 +1,42 @@
+use crate::{prelude::Sound, Vec};
+
+use super::Pattern;
+
+/// All the data needed to play a song: instruments, patterns, the order in which
+/// patterns are played and timing.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Song {
+    /// Sounds that can be triggered by pattern cells, using their index.
+    pub instruments: Vec<Sound>,
+    /// Patterns referenced by the order list.
+    pub patterns: Vec<Pattern>,
+    /// Indices into the pattern list, in playback order.
+    pub order: Vec<usize>,
+    /// Order list index to jump to after the last pattern ends. "None" stops playback.
+    pub loop_order: Option<usize>,
+    /// Ticks per row. Lower is faster.
+    pub speed: u8,
+    /// Tracker-style tempo, where the tick rate in Hz is "tempo * 2 / 5",
+    /// i.e. the default 125 means 50 ticks per second.
+    pub tempo: f32,
+}
+
+impl Default for Song {
+    fn default() -> Self {
+        Self {
+            instruments: Vec::new(),
+            patterns: Vec::new(),
+            order: Vec::new(),
+            loop_order: None,
+            speed: 6,
+            tempo: 125.0,
+        }
+    }
+}
+
+impl Song {
+    /// How many ticks per second, derived from the tempo.
+    pub fn tick_rate(&self) -> f32 {
+        (self.tempo * 2.0 / 5.0).max(f32::EPSILON)
+    }
+}
@@ -0,0 +1,134 @@
+//! RIFF WAV reading and writing, without the standard library. Files are written to and read
+//! from byte buffers, saving and loading them is left to the application.
+//!
+//! [WavWriter] records mono or stereo [SoundChip](crate::prelude::SoundChip) output as 8, 16
+//! or 24 bit integers, or 32 bit floats. [WavFile] reads the same formats, plus the loop points
+//! and root note from a "smpl" chunk, and converts them into a [PcmSample](crate::prelude::PcmSample)
+//! or raw values for [Channel::set_wavetable_raw()](crate::prelude::Channel::set_wavetable_raw).
+
+mod reader;
+pub use reader::*;
+
+mod writer;
+pub use writer::*;
+
+const FORMAT_PCM: u16 = 1;
+const FORMAT_FLOAT: u16 = 3;
+const FORMAT_EXTENSIBLE: u16 = 0xFFFE;
+
+/// Sample encoding of a WAV file.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum WavFormat {
+    /// Unsigned 8 bit integers.
+    Int8,
+    #[default]
+    /// Signed 16 bit integers.
+    Int16,
+    /// Signed 24 bit integers.
+    Int24,
+    /// 32 bit floats.
+    Float32,
+}
+
+impl WavFormat {
+    /// Size of a single value in bits.
+    pub fn bits_per_sample(self) -> u16 {
+        match self {
+            WavFormat::Int8 => 8,
+            WavFormat::Int16 => 16,
+            WavFormat::Int24 => 24,
+            WavFormat::Float32 => 32,
+        }
+    }
+
+    /// Size of a single value in bytes.
+    pub fn bytes_per_sample(self) -> usize {
+        self.bits_per_sample() as usize / 8
+    }
+
+    fn format_tag(self) -> u16 {
+        match self {
+            WavFormat::Float32 => FORMAT_FLOAT,
+            _ => FORMAT_PCM,
+        }
+    }
+
+    fn from_tag(tag: u16, bits: u16) -> Option<Self> {
+        match (tag, bits) {
+            (FORMAT_PCM, 8) => Some(WavFormat::Int8),
+            (FORMAT_PCM, 16) => Some(WavFormat::Int16),
+            (FORMAT_PCM, 24) => Some(WavFormat::Int24),
+            (FORMAT_FLOAT, 32) => Some(WavFormat::Float32),
+            _ => None,
+        }
+    }
+}
+
+/// Loop points from a "smpl" chunk, in frames. "end" is exclusive.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WavLoop {
+    pub start: usize,
+    pub end: usize,
+}
+
+#[test]
+fn wav_round_trip() {
+    use crate::prelude::{PcmLoop, Sample};
+    for format in [
+        WavFormat::Int8,
+        WavFormat::Int16,
+        WavFormat::Int24,
+        WavFormat::Float32,
+    ] {
+        let mut writer = WavWriter::new(format, 22050, 2);
+        writer.set_loop(Some(WavLoop { start: 2, end: 4 }));
+        writer.set_root_note(Some(48));
+        writer.write_frame(0.5, -0.5);
+        writer.write(Sample {
+            left: i16::MAX,
+            right: i16::MIN,
+        });
+        writer.write_frame(0.0, 0.25);
+        writer.write_frame(0.0, 0.0);
+        let bytes = writer.to_bytes();
+
+        let file = WavFile::parse(&bytes).unwrap();
+        assert_eq!(file.format, format);
+        assert_eq!(file.sample_rate, 22050);
+        assert_eq!(file.channels, 2);
+        assert_eq!(file.frames(), 4);
+        assert_eq!(file.loop_points, Some(WavLoop { start: 2, end: 4 }));
+        assert_eq!(file.root_note, Some(48));
+        let tolerance = 1.0 / 64.0;
+        let left = file.channel(0);
+        let right = file.channel(1);
+        assert!((left[0] - 0.5).abs() < tolerance);
+        assert!((right[0] + 0.5).abs() < tolerance);
+        assert!((left[1] - 1.0).abs() < tolerance);
+        assert!((right[1] + 1.0).abs() < tolerance);
+        assert!((right[2] - 0.25).abs() < tolerance);
+
+        let pcm = file.to_pcm();
+        assert_eq!(pcm.data.len(), 4);
+        assert_eq!(pcm.root_note, 48.0);
+        assert_eq!(pcm.loop_kind, PcmLoop::Forward { start: 2, end: 4 });
+    }
+}
+
+#[test]
+fn wav_truncated_data() {
+    let mut writer = WavWriter::new(WavFormat::Int16, 22050, 1);
+    for _ in 0..4 {
+        writer.write_frame(0.5, 0.5);
+    }
+    let mut bytes = writer.to_bytes();
+
+    // Unset data length, as left by some streaming recorders.
+    bytes[40..44].copy_from_slice(&u32::MAX.to_le_bytes());
+    assert_eq!(WavFile::parse(&bytes).unwrap().frames(), 4);
+
+    // File cut off in the middle of the data.
+    bytes[40..44].copy_from_slice(&8u32.to_le_bytes());
+    bytes.truncate(bytes.len() - 3);
+    assert_eq!(WavFile::parse(&bytes).unwrap().frames(), 2);
+}
@@ -0,0 +1,162 @@
+use super::{WavFormat, WavLoop, FORMAT_EXTENSIBLE};
+use crate::{
+    prelude::{ChipError, PcmLoop, PcmSample},
+    Vec,
+};
+
+/// A parsed WAV file, with values converted to f32.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WavFile {
+    pub format: WavFormat,
+    pub sample_rate: u32,
+    pub channels: u16,
+    /// Interleaved values from -1.0 to 1.0, i.e. left, right, left, right for stereo files.
+    pub samples: Vec<f32>,
+    /// First loop found in the "smpl" chunk, if any.
+    pub loop_points: Option<WavLoop>,
+    /// MIDI unity note from the "smpl" chunk, if any.
+    pub root_note: Option<u8>,
+}
+
+impl WavFile {
+    /// Parses a RIFF WAV file. Returns [ChipError::InvalidWav] if the data is malformed
+    /// or uses an unsupported format. A truncated last chunk is read up to the end of the data.
+    pub fn parse(data: &[u8]) -> Result<Self, ChipError> {
+        if data.len() < 12 || &data[0..4] != b"RIFF" || &data[8..12] != b"WAVE" {
+            return Err(ChipError::InvalidWav);
+        }
+        let mut format = None;
+        let mut sample_rate = 0;
+        let mut channels = 0;
+        let mut samples = None;
+        let mut loop_points = None;
+        let mut root_note = None;
+
+        let mut head = 12;
+        while head + 8 <= data.len() {
+            let id = &data[head..head + 4];
+            let len = read_u32(data, head + 4)? as usize;
+            let start = head + 8;
+            // Recorders that stream to disk may leave the final length unset or too long,
+            // so chunks running past the end are cut short instead.
+            let end = start
+                .checked_add(len)
+                .map_or(data.len(), |end| end.min(data.len()));
+            let chunk = &data[start..end];
+            match id {
+                b"fmt " => {
+                    if chunk.len() < 16 {
+                        return Err(ChipError::InvalidWav);
+                    }
+                    let mut tag = read_u16(chunk, 0)?;
+                    channels = read_u16(chunk, 2)?;
+                    sample_rate = read_u32(chunk, 4)?;
+                    let bits = read_u16(chunk, 14)?;
+                    if tag == FORMAT_EXTENSIBLE {
+                        // The actual format is the start of the sub format GUID
+                        tag = read_u16(chunk, 24)?;
+                    }
+                    format = WavFormat::from_tag(tag, bits);
+                    if format.is_none() || channels == 0 {
+                        return Err(ChipError::InvalidWav);
+                    }
+                }
+                b"data" => {
+                    let format = format.ok_or(ChipError::InvalidWav)?;
+                    samples = Some(decode(format, chunk));
+                }
+                b"smpl" if chunk.len() >= 36 => {
+                    root_note = Some(read_u32(chunk, 12)?.min(127) as u8);
+                    let loop_count = read_u32(chunk, 28)?;
+                    if loop_count > 0 && chunk.len() >= 36 + 24 {
+                        let start = read_u32(chunk, 36 + 8)? as usize;
+                        let end = read_u32(chunk, 36 + 12)? as usize;
+                        loop_points = Some(WavLoop {
+                            start,
+                            end: end + 1,
+                        });
+                    }
+                }
+                _ => {}
+            }
+            // Chunks are padded to an even length
+            head = end + (len % 2);
+        }
+
+        Ok(Self {
+            format: format.ok_or(ChipError::InvalidWav)?,
+            sample_rate,
+            channels,
+            samples: samples.ok_or(ChipError::InvalidWav)?,
+            loop_points,
+            root_note,
+        })
+    }
+
+    /// Number of frames (samples per channel).
+    pub fn frames(&self) -> usize {
+        self.samples.len() / self.channels as usize
+    }
+
+    /// Values of a single channel, i.e. 0 for left and 1 for right.
+    pub fn channel(&self, index: usize) -> Vec<f32> {
+        self.samples
+            .iter()
+            .skip(index)
+            .step_by(self.channels as usize)
+            .copied()
+            .collect()
+    }
+
+    /// All channels mixed into one, which can be used with
+    /// [Channel::set_wavetable_raw()](crate::prelude::Channel::set_wavetable_raw).
+    pub fn mono(&self) -> Vec<f32> {
+        self.samples
+            .chunks(self.channels as usize)
+            .map(|frame| frame.iter().sum::<f32>() / frame.len() as f32)
+            .collect()
+    }
+
+    /// Converts to a mono [PcmSample], using the "smpl" chunk's root note and loop if present.
+    pub fn to_pcm(&self) -> PcmSample {
+        let data = self.mono();
+        let loop_kind = match self.loop_points {
+            Some(WavLoop { start, end }) if start < end && end <= data.len() => {
+                PcmLoop::Forward { start, end }
+            }
+            _ => PcmLoop::OneShot,
+        };
+        let pcm = PcmSample::new(data, self.sample_rate as f32).with_loop(loop_kind);
+        if let Some(note) = self.root_note {
+            pcm.with_root_note(note as f32)
+        } else {
+            pcm
+        }
+    }
+}
+
+fn decode(format: WavFormat, data: &[u8]) -> Vec<f32> {
+    let size = format.bytes_per_sample();
+    data.chunks_exact(size)
+        .map(|bytes| match format {
+            WavFormat::Int8 => (bytes[0] as f32 - 128.0) / 128.0,
+            WavFormat::Int16 => i16::from_le_bytes([bytes[0], bytes[1]]) as f32 / 32768.0,
+            WavFormat::Int24 => {
+                // Sign extended by placing the bytes at the top of an i32
+                let value = i32::from_le_bytes([0, bytes[0], bytes[1], bytes[2]]) >> 8;
+                value as f32 / 8388608.0
+            }
+            WavFormat::Float32 => f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]),
+        })
+        .collect()
+}
+
+fn read_u16(data: &[u8], head: usize) -> Result<u16, ChipError> {
+    let bytes = data.get(head..head + 2).ok_or(ChipError::InvalidWav)?;
+    Ok(u16::from_le_bytes([bytes[0], bytes[1]]))
+}
+
+fn read_u32(data: &[u8], head: usize) -> Result<u32, ChipError> {
+    let bytes = data.get(head..head + 4).ok_or(ChipError::InvalidWav)?;
+    Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+}
@@ -0,0 +1,144 @@
+use super::{WavFormat, WavLoop};
+use crate::{prelude::Sample, Vec};
+
+/// Accumulates audio frames in memory and produces a WAV file as bytes.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WavWriter {
+    format: WavFormat,
+    sample_rate: u32,
+    channels: u16,
+    data: Vec<u8>,
+    loop_points: Option<WavLoop>,
+    root_note: Option<u8>,
+}
+
+impl WavWriter {
+    /// A new, empty writer. "channels" is clamped to 1 (mono) or 2 (stereo).
+    pub fn new(format: WavFormat, sample_rate: u32, channels: u16) -> Self {
+        Self {
+            format,
+            sample_rate,
+            channels: channels.clamp(1, 2),
+            data: Vec::new(),
+            loop_points: None,
+            root_note: None,
+        }
+    }
+
+    /// Number of frames written so far.
+    pub fn frames(&self) -> usize {
+        self.data.len() / (self.format.bytes_per_sample() * self.channels as usize)
+    }
+
+    /// Loop points stored in a "smpl" chunk. None writes no loop.
+    pub fn set_loop(&mut self, loop_points: Option<WavLoop>) {
+        self.loop_points = loop_points;
+    }
+
+    /// MIDI root note stored in a "smpl" chunk.
+    pub fn set_root_note(&mut self, root_note: Option<u8>) {
+        self.root_note = root_note;
+    }
+
+    /// Writes a single [SoundChip](crate::prelude::SoundChip) output sample.
+    /// Mono files receive the average of both sides.
+    pub fn write(&mut self, sample: Sample<i16>) {
+        self.write_frame(sample.left as f32 / 32768.0, sample.right as f32 / 32768.0);
+    }
+
+    /// Writes a single frame with values from -1.0 to 1.0.
+    /// Mono files receive the average of both sides.
+    pub fn write_frame(&mut self, left: f32, right: f32) {
+        if self.channels == 1 {
+            self.write_value((left + right) / 2.0);
+        } else {
+            self.write_value(left);
+            self.write_value(right);
+        }
+    }
+
+    fn write_value(&mut self, value: f32) {
+        let value = value.clamp(-1.0, 1.0);
+        match self.format {
+            WavFormat::Int8 => {
+                let int = libm::roundf(value * 128.0).clamp(-128.0, 127.0) as i16;
+                self.data.push((int + 128) as u8);
+            }
+            WavFormat::Int16 => {
+                let int = libm::roundf(value * 32768.0).clamp(-32768.0, 32767.0) as i16;
+                self.data.extend_from_slice(&int.to_le_bytes());
+            }
+            WavFormat::Int24 => {
+                let int = libm::roundf(value * 8388608.0).clamp(-8388608.0, 8388607.0) as i32;
+                self.data.extend_from_slice(&int.to_le_bytes()[0..3]);
+            }
+            WavFormat::Float32 => {
+                self.data.extend_from_slice(&value.to_le_bytes());
+            }
+        }
+    }
+
+    /// The complete file, ready to be saved.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let block_align = self.format.bytes_per_sample() as u16 * self.channels;
+        let pad = self.data.len() % 2;
+        let smpl_len = if self.loop_points.is_some() || self.root_note.is_some() {
+            8 + 36 + if self.loop_points.is_some() { 24 } else { 0 }
+        } else {
+            0
+        };
+        let riff_len = 4 + (8 + 16) + (8 + self.data.len() + pad) + smpl_len;
+
+        let mut bytes = Vec::with_capacity(8 + riff_len);
+        bytes.extend_from_slice(b"RIFF");
+        push_u32(&mut bytes, riff_len as u32);
+        bytes.extend_from_slice(b"WAVE");
+
+        bytes.extend_from_slice(b"fmt ");
+        push_u32(&mut bytes, 16);
+        push_u16(&mut bytes, self.format.format_tag());
+        push_u16(&mut bytes, self.channels);
+        push_u32(&mut bytes, self.sample_rate);
+        push_u32(&mut bytes, self.sample_rate * block_align as u32);
+        push_u16(&mut bytes, block_align);
+        push_u16(&mut bytes, self.format.bits_per_sample());
+
+        bytes.extend_from_slice(b"data");
+        push_u32(&mut bytes, self.data.len() as u32);
+        bytes.extend_from_slice(&self.data);
+        if pad > 0 {
+            bytes.push(0);
+        }
+
+        if smpl_len > 0 {
+            bytes.extend_from_slice(b"smpl");
+            push_u32(&mut bytes, smpl_len as u32 - 8);
+            push_u32(&mut bytes, 0); // Manufacturer
+            push_u32(&mut bytes, 0); // Product
+            push_u32(&mut bytes, 1_000_000_000 / self.sample_rate.max(1)); // Sample period in ns
+            push_u32(&mut bytes, self.root_note.unwrap_or(60) as u32);
+            push_u32(&mut bytes, 0); // Pitch fraction
+            push_u32(&mut bytes, 0); // SMPTE format
+            push_u32(&mut bytes, 0); // SMPTE offset
+            push_u32(&mut bytes, self.loop_points.is_some() as u32);
+            push_u32(&mut bytes, 0); // Sampler data
+            if let Some(loop_points) = self.loop_points {
+                push_u32(&mut bytes, 0); // Cue point ID
+                push_u32(&mut bytes, 0); // Forward loop
+                push_u32(&mut bytes, loop_points.start as u32);
+                push_u32(&mut bytes, loop_points.end.saturating_sub(1) as u32); // Inclusive
+                push_u32(&mut bytes, 0); // Fraction
+                push_u32(&mut bytes, 0); // Play count, infinite
+            }
+        }
+        bytes
+    }
+}
+
+fn push_u16(bytes: &mut Vec<u8>, value: u16) {
+    bytes.extend_from_slice(&value.to_le_bytes());
+}
+
+fn push_u32(bytes: &mut Vec<u8>, value: u32) {
+    bytes.extend_from_slice(&value.to_le_bytes());
+}